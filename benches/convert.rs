@@ -0,0 +1,32 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use ourairports::Airport;
+
+/// Sample of the airport data
+const AIRPORTS_CSV: &str = include_str!("fixtures/airports.csv");
+
+/// Parses the sample airport data
+fn parse_airports(data: &str) -> Vec<Airport> {
+    csv::Reader::from_reader(data.as_bytes())
+        .deserialize()
+        .collect::<Result<_, _>>()
+        .unwrap()
+}
+
+fn bench_parse(c: &mut Criterion) {
+    c.bench_function("parse airports", |b| {
+        b.iter(|| parse_airports(black_box(AIRPORTS_CSV)))
+    });
+}
+
+fn bench_serialize(c: &mut Criterion) {
+    let airports = parse_airports(AIRPORTS_CSV);
+    c.bench_function("serialize airports compact", |b| {
+        b.iter(|| serde_json::to_string(black_box(&airports)).unwrap())
+    });
+    c.bench_function("serialize airports pretty", |b| {
+        b.iter(|| serde_json::to_string_pretty(black_box(&airports)).unwrap())
+    });
+}
+
+criterion_group!(benches, bench_parse, bench_serialize);
+criterion_main!(benches);
@@ -0,0 +1,37 @@
+use crate::enrich::looks_like_icao;
+use crate::Airport;
+use std::collections::BTreeMap;
+
+/// Maps the IATA code of each airport to its ICAO code, as guessed by `Airport::guess_icao_code`.
+/// Airports without an IATA code, or without anything that looks like an ICAO code, are skipped.
+/// If several airports share a code, the first one is used.
+pub fn iata_to_icao(airports: &[Airport]) -> BTreeMap<String, String> {
+    let mut codes = BTreeMap::new();
+    for (iata_code, icao_code) in code_pairs(airports) {
+        codes
+            .entry(iata_code.to_string())
+            .or_insert_with(|| icao_code.to_string());
+    }
+    codes
+}
+
+/// Maps the ICAO code of each airport, as guessed by `Airport::guess_icao_code`, to its IATA code.
+/// Airports without an IATA code, or without anything that looks like an ICAO code, are skipped.
+/// If several airports share a code, the first one is used.
+pub fn icao_to_iata(airports: &[Airport]) -> BTreeMap<String, String> {
+    let mut codes = BTreeMap::new();
+    for (iata_code, icao_code) in code_pairs(airports) {
+        codes
+            .entry(icao_code.to_string())
+            .or_insert_with(|| iata_code.to_string());
+    }
+    codes
+}
+
+/// Returns the IATA and ICAO codes of the airports that have both
+fn code_pairs(airports: &[Airport]) -> impl Iterator<Item = (&str, &str)> {
+    airports
+        .iter()
+        .map(|airport| (airport.iata_code.as_str(), airport.guess_icao_code()))
+        .filter(|(iata_code, icao_code)| !iata_code.is_empty() && looks_like_icao(icao_code))
+}
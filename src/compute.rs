@@ -0,0 +1,94 @@
+use evalexpr::{ContextWithMutableVariables, EvalexprError, HashMapContext, Node};
+use serde_json::{Map, Value};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// A field computed from the other fields of a record with an expression,
+/// written as `NAME=EXPR`, e.g. `elev_m=elevation_ft * 0.3048`
+#[derive(Clone, Debug)]
+pub struct ComputedField {
+    /// The name of the field.
+    pub name: String,
+    expression: Node,
+}
+
+/// The error when a string isn't a computed field
+#[derive(Debug)]
+pub struct ParseComputedFieldError(String);
+
+impl fmt::Display for ParseComputedFieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Error for ParseComputedFieldError {}
+
+impl FromStr for ComputedField {
+    type Err = ParseComputedFieldError;
+
+    /// Parses a computed field written as `NAME=EXPR`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (name, expression) = s
+            .split_once('=')
+            .map(|(name, expression)| (name.trim(), expression))
+            .filter(|(name, _)| !name.is_empty())
+            .ok_or_else(|| {
+                ParseComputedFieldError(format!("invalid computed field {}, expected NAME=EXPR", s))
+            })?;
+        let expression = evalexpr::build_operator_tree(expression).map_err(|err| {
+            ParseComputedFieldError(format!("invalid expression for {}: {}", name, err))
+        })?;
+        Ok(ComputedField {
+            name: name.to_string(),
+            expression,
+        })
+    }
+}
+
+/// Converts a JSON value to an expression value, or None for values expressions can't use
+fn to_expression_value(value: &Value) -> Option<evalexpr::Value> {
+    match value {
+        Value::Null => Some(evalexpr::Value::Empty),
+        Value::Bool(value) => Some(evalexpr::Value::Boolean(*value)),
+        Value::Number(number) => number
+            .as_i64()
+            .map(evalexpr::Value::Int)
+            .or_else(|| number.as_f64().map(evalexpr::Value::Float)),
+        Value::String(value) => Some(evalexpr::Value::String(value.clone())),
+        Value::Array(values) => values
+            .iter()
+            .map(to_expression_value)
+            .collect::<Option<_>>()
+            .map(evalexpr::Value::Tuple),
+        Value::Object(_) => None,
+    }
+}
+
+/// Converts an expression value to a JSON value. Floats that aren't finite become null.
+fn to_json_value(value: evalexpr::Value) -> Value {
+    match value {
+        evalexpr::Value::String(value) => value.into(),
+        evalexpr::Value::Float(value) => value.into(),
+        evalexpr::Value::Int(value) => value.into(),
+        evalexpr::Value::Boolean(value) => value.into(),
+        evalexpr::Value::Tuple(values) => values.into_iter().map(to_json_value).collect(),
+        evalexpr::Value::Empty => Value::Null,
+    }
+}
+
+impl ComputedField {
+    /// Evaluates the expression with the fields of the record as variables
+    pub fn compute(&self, record: &Map<String, Value>) -> Result<Value, EvalexprError> {
+        let mut context = HashMapContext::new();
+        for (key, value) in record {
+            if let Some(value) = to_expression_value(value) {
+                context.set_value(key.clone(), value)?;
+            }
+        }
+        self.expression
+            .eval_with_context(&context)
+            .map(to_json_value)
+    }
+}
@@ -0,0 +1,76 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// One of the continents used by OurAirports
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Continent {
+    Africa,
+    Antarctica,
+    Asia,
+    Europe,
+    NorthAmerica,
+    Oceania,
+    SouthAmerica,
+}
+
+impl Continent {
+    /// All of the continents
+    pub const ALL: [Continent; 7] = [
+        Continent::Africa,
+        Continent::Antarctica,
+        Continent::Asia,
+        Continent::Europe,
+        Continent::NorthAmerica,
+        Continent::Oceania,
+        Continent::SouthAmerica,
+    ];
+
+    /// Returns the two-character code of the continent, as used in the `continent` fields
+    pub fn code(&self) -> &'static str {
+        match self {
+            Continent::Africa => "AF",
+            Continent::Antarctica => "AN",
+            Continent::Asia => "AS",
+            Continent::Europe => "EU",
+            Continent::NorthAmerica => "NA",
+            Continent::Oceania => "OC",
+            Continent::SouthAmerica => "SA",
+        }
+    }
+}
+
+impl fmt::Display for Continent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// The error when a string isn't one of the continent codes
+#[derive(Debug)]
+pub struct ParseContinentError(String);
+
+impl fmt::Display for ParseContinentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown continent code {}, expected one of AF, AN, AS, EU, NA, OC or SA",
+            self.0
+        )
+    }
+}
+
+impl Error for ParseContinentError {}
+
+impl FromStr for Continent {
+    type Err = ParseContinentError;
+
+    /// Parses a continent code, ignoring case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Continent::ALL
+            .iter()
+            .copied()
+            .find(|continent| continent.code().eq_ignore_ascii_case(s))
+            .ok_or_else(|| ParseContinentError(s.to_string()))
+    }
+}
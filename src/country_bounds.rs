@@ -0,0 +1,317 @@
+use crate::distance_nm;
+
+/// The coarse bounding box of a country, including its outlying islands, in degrees
+pub(crate) struct CountryBounds {
+    /// The southmost latitude.
+    pub south: f64,
+    /// The northmost latitude.
+    pub north: f64,
+    /// The westmost longitude, which is greater than `east` for countries across the antimeridian.
+    pub west: f64,
+    /// The eastmost longitude.
+    pub east: f64,
+}
+
+impl CountryBounds {
+    /// Returns whether a longitude is between the west and east of the bounds
+    fn contains_longitude(&self, longitude: f64) -> bool {
+        if self.west <= self.east {
+            (self.west..=self.east).contains(&longitude)
+        } else {
+            longitude >= self.west || longitude <= self.east
+        }
+    }
+
+    /// Returns roughly how far a point is outside the bounds, from the nearest latitude and longitude
+    /// within them, in nautical miles. Points within the bounds are 0 NM away.
+    pub fn distance_nm(&self, latitude: f64, longitude: f64) -> f64 {
+        let nearest_longitude = if self.contains_longitude(longitude) {
+            longitude
+        } else if degrees_apart(longitude, self.west) < degrees_apart(longitude, self.east) {
+            self.west
+        } else {
+            self.east
+        };
+        distance_nm(
+            latitude,
+            longitude,
+            latitude.clamp(self.south, self.north),
+            nearest_longitude,
+        )
+    }
+}
+
+/// Returns how many degrees apart two longitudes are, the shorter way around
+fn degrees_apart(a: f64, b: f64) -> f64 {
+    let difference = (a - b).rem_euclid(360.0);
+    difference.min(360.0 - difference)
+}
+
+/// Returns the bounds of the country with this `iso_country` code, or None if it isn't known
+pub(crate) fn country_bounds(code: &str) -> Option<CountryBounds> {
+    let index = COUNTRY_BOUNDS
+        .binary_search_by_key(&code, |&(bounds_code, ..)| bounds_code)
+        .ok()?;
+    let (_, south, north, west, east) = COUNTRY_BOUNDS[index];
+    Some(CountryBounds {
+        south,
+        north,
+        west,
+        east,
+    })
+}
+
+/// The code, south, north, west and east of each country, sorted by code.
+/// Rounded outwards to a tenth of a degree, and covering the islands that share the code of a country.
+const COUNTRY_BOUNDS: [(&str, f64, f64, f64, f64); 250] = [
+    ("AD", 42.4, 42.7, 1.4, 1.8),
+    ("AE", 22.6, 26.1, 51.5, 56.4),
+    ("AF", 29.3, 38.5, 60.5, 75.0),
+    ("AG", 16.9, 17.8, -62.4, -61.6),
+    ("AI", 18.1, 18.6, -63.5, -62.9),
+    ("AL", 39.6, 42.7, 19.2, 21.1),
+    ("AM", 38.8, 41.3, 43.4, 46.7),
+    ("AO", -18.1, -4.3, 11.6, 24.1),
+    ("AQ", -90.0, -60.0, -180.0, 180.0),
+    ("AR", -55.1, -21.7, -73.6, -53.6),
+    ("AS", -14.6, -11.0, -171.2, -168.1),
+    ("AT", 46.3, 49.1, 9.5, 17.2),
+    ("AU", -55.2, -9.1, 112.9, 159.3),
+    ("AW", 12.4, 12.7, -70.1, -69.8),
+    ("AX", 59.7, 60.7, 19.3, 21.3),
+    ("AZ", 38.4, 41.9, 44.7, 50.6),
+    ("BA", 42.5, 45.3, 15.7, 19.7),
+    ("BB", 13.0, 13.4, -59.7, -59.4),
+    ("BD", 20.6, 26.7, 88.0, 92.7),
+    ("BE", 49.5, 51.6, 2.5, 6.4),
+    ("BF", 9.4, 15.1, -5.6, 2.4),
+    ("BG", 41.2, 44.3, 22.3, 28.6),
+    ("BH", 25.5, 26.4, 50.3, 50.9),
+    ("BI", -4.5, -2.3, 29.0, 30.9),
+    ("BJ", 6.2, 12.5, 0.7, 3.9),
+    ("BL", 17.8, 18.0, -63.0, -62.7),
+    ("BM", 32.2, 32.5, -64.9, -64.6),
+    ("BN", 4.0, 5.1, 114.0, 115.4),
+    ("BO", -22.9, -9.6, -69.7, -57.4),
+    ("BQ", 12.0, 17.7, -68.5, -62.9),
+    ("BR", -33.8, 5.3, -74.0, -28.8),
+    ("BS", 20.9, 27.3, -79.3, -72.7),
+    ("BT", 26.7, 28.4, 88.7, 92.2),
+    ("BV", -54.5, -54.3, 3.3, 3.5),
+    ("BW", -26.9, -17.8, 20.0, 29.4),
+    ("BY", 51.2, 56.2, 23.1, 32.8),
+    ("BZ", 15.8, 18.5, -89.3, -87.5),
+    ("CA", 41.6, 83.2, -141.1, -52.6),
+    ("CC", -12.3, -11.8, 96.8, 97.0),
+    ("CD", -13.5, 5.4, 12.2, 31.4),
+    ("CF", 2.2, 11.0, 14.4, 27.5),
+    ("CG", -5.1, 3.8, 11.1, 18.7),
+    ("CH", 45.8, 47.9, 5.9, 10.5),
+    ("CI", 4.3, 10.8, -8.7, -2.5),
+    ("CK", -22.0, -8.9, -166.0, -157.3),
+    ("CL", -56.0, -17.5, -109.5, -66.4),
+    ("CM", 1.6, 13.1, 8.4, 16.2),
+    ("CN", 9.0, 53.6, 73.5, 134.8),
+    ("CO", -4.3, 13.4, -81.8, -66.8),
+    ("CR", 5.5, 11.3, -87.1, -82.5),
+    ("CU", 19.8, 23.3, -85.0, -74.1),
+    ("CV", 14.8, 17.2, -25.4, -22.6),
+    ("CW", 12.0, 12.4, -69.2, -68.7),
+    ("CX", -10.6, -10.4, 105.5, 105.8),
+    ("CY", 34.5, 35.7, 32.2, 34.6),
+    ("CZ", 48.5, 51.1, 12.1, 18.9),
+    ("DE", 47.2, 55.1, 5.8, 15.1),
+    ("DJ", 10.9, 12.8, 41.7, 43.5),
+    ("DK", 54.5, 57.8, 8.0, 15.2),
+    ("DM", 15.2, 15.7, -61.5, -61.2),
+    ("DO", 17.5, 20.0, -72.1, -68.3),
+    ("DZ", 18.9, 37.1, -8.7, 12.0),
+    ("EC", -5.1, 1.7, -92.1, -75.2),
+    ("EE", 57.5, 59.7, 21.7, 28.3),
+    ("EG", 21.9, 31.7, 24.7, 36.9),
+    ("EH", 20.7, 27.7, -17.2, -8.6),
+    ("ER", 12.3, 18.0, 36.4, 43.2),
+    ("ES", 27.6, 43.8, -18.2, 4.4),
+    ("ET", 3.4, 15.0, 32.9, 48.0),
+    ("FI", 59.8, 70.1, 20.5, 31.6),
+    ("FJ", -21.1, -12.4, 176.8, -178.2),
+    ("FK", -52.5, -51.0, -61.4, -57.7),
+    ("FM", 1.0, 10.1, 137.3, 163.1),
+    ("FO", 61.4, 62.4, -7.7, -6.3),
+    ("FR", 41.3, 51.1, -5.2, 9.6),
+    ("GA", -4.0, 2.3, 8.7, 14.6),
+    ("GB", 49.8, 60.9, -8.7, 1.8),
+    ("GD", 11.9, 12.6, -61.8, -61.4),
+    ("GE", 41.0, 43.6, 40.0, 46.8),
+    ("GF", 2.1, 5.8, -54.6, -51.6),
+    ("GG", 49.4, 49.8, -2.7, -2.2),
+    ("GH", 4.7, 11.2, -3.3, 1.2),
+    ("GI", 36.1, 36.2, -5.4, -5.3),
+    ("GL", 59.7, 83.7, -73.1, -11.3),
+    ("GM", 13.0, 13.9, -16.9, -13.8),
+    ("GN", 7.2, 12.7, -15.1, -7.6),
+    ("GP", 15.8, 16.6, -61.9, -61.0),
+    ("GQ", -1.5, 3.8, 5.6, 11.4),
+    ("GR", 34.8, 41.8, 19.3, 29.7),
+    ("GS", -59.5, -53.9, -38.3, -26.2),
+    ("GT", 13.7, 17.9, -92.3, -88.2),
+    ("GU", 13.2, 13.7, 144.6, 145.0),
+    ("GW", 10.9, 12.7, -16.8, -13.6),
+    ("GY", 1.1, 8.6, -61.4, -56.4),
+    ("HK", 22.1, 22.6, 113.8, 114.5),
+    ("HM", -53.2, -52.9, 73.2, 73.9),
+    ("HN", 12.9, 17.5, -89.4, -83.1),
+    ("HR", 42.3, 46.6, 13.4, 19.5),
+    ("HT", 17.9, 20.1, -74.5, -71.6),
+    ("HU", 45.7, 48.6, 16.1, 22.9),
+    ("ID", -11.1, 6.1, 95.0, 141.1),
+    ("IE", 51.4, 55.4, -10.7, -5.9),
+    ("IL", 29.4, 33.4, 34.2, 35.9),
+    ("IM", 54.0, 54.5, -4.9, -4.3),
+    ("IN", 6.7, 35.7, 68.1, 97.4),
+    ("IO", -7.5, -5.2, 71.2, 72.5),
+    ("IQ", 29.0, 37.4, 38.8, 48.6),
+    ("IR", 25.0, 39.8, 44.0, 63.4),
+    ("IS", 63.3, 66.6, -24.6, -13.4),
+    ("IT", 35.4, 47.1, 6.6, 18.5),
+    ("JE", 49.1, 49.3, -2.3, -2.0),
+    ("JM", 17.7, 18.6, -78.4, -76.2),
+    ("JO", 29.1, 33.4, 34.9, 39.3),
+    ("JP", 20.4, 45.6, 122.9, 154.0),
+    ("KE", -4.7, 5.1, 33.9, 41.9),
+    ("KG", 39.1, 43.3, 69.2, 80.3),
+    ("KH", 10.4, 14.7, 102.3, 107.7),
+    ("KI", -11.5, 4.8, 169.5, -150.2),
+    ("KM", -12.5, -11.3, 43.2, 44.6),
+    ("KN", 17.1, 17.5, -62.9, -62.5),
+    ("KP", 37.6, 43.0, 124.2, 130.7),
+    ("KR", 33.1, 38.7, 124.6, 131.9),
+    ("KW", 28.5, 30.1, 46.5, 48.5),
+    ("KY", 19.2, 19.8, -81.5, -79.7),
+    ("KZ", 40.5, 55.5, 46.4, 87.4),
+    ("LA", 13.9, 22.5, 100.0, 107.7),
+    ("LB", 33.0, 34.7, 35.1, 36.7),
+    ("LC", 13.7, 14.2, -61.1, -60.8),
+    ("LI", 47.0, 47.3, 9.4, 9.7),
+    ("LK", 5.9, 9.9, 79.5, 81.9),
+    ("LR", 4.3, 8.6, -11.5, -7.3),
+    ("LS", -30.7, -28.5, 27.0, 29.5),
+    ("LT", 53.8, 56.5, 20.9, 26.9),
+    ("LU", 49.4, 50.2, 5.7, 6.6),
+    ("LV", 55.6, 58.1, 20.9, 28.3),
+    ("LY", 19.5, 33.2, 9.3, 25.2),
+    ("MA", 27.6, 35.9, -13.2, -1.0),
+    ("MC", 43.7, 43.8, 7.4, 7.5),
+    ("MD", 45.4, 48.5, 26.6, 30.2),
+    ("ME", 41.8, 43.6, 18.4, 20.4),
+    ("MF", 18.0, 18.2, -63.2, -63.0),
+    ("MG", -25.7, -11.9, 43.2, 50.5),
+    ("MH", 4.5, 14.7, 160.8, 172.2),
+    ("MK", 40.8, 42.4, 20.4, 23.1),
+    ("ML", 10.1, 25.0, -12.3, 4.3),
+    ("MM", 9.6, 28.6, 92.1, 101.2),
+    ("MN", 41.5, 52.2, 87.7, 119.9),
+    ("MO", 22.1, 22.2, 113.5, 113.6),
+    ("MP", 14.1, 20.6, 144.8, 146.1),
+    ("MQ", 14.3, 14.9, -61.3, -60.8),
+    ("MR", 14.7, 27.3, -17.1, -4.8),
+    ("MS", 16.6, 16.9, -62.3, -62.1),
+    ("MT", 35.8, 36.1, 14.1, 14.6),
+    ("MU", -20.6, -10.3, 56.5, 63.6),
+    ("MV", -0.7, 7.1, 72.6, 73.8),
+    ("MW", -17.2, -9.3, 32.6, 35.9),
+    ("MX", 14.5, 32.8, -118.4, -86.7),
+    ("MY", 0.8, 7.4, 99.6, 119.3),
+    ("MZ", -26.9, -10.4, 30.2, 40.9),
+    ("NA", -29.0, -16.9, 11.7, 25.3),
+    ("NC", -22.7, -17.9, 158.0, 169.0),
+    ("NE", 11.6, 23.6, 0.1, 16.0),
+    ("NF", -29.2, -28.9, 167.9, 168.0),
+    ("NG", 4.2, 13.9, 2.6, 14.7),
+    ("NI", 10.7, 15.1, -87.7, -82.6),
+    ("NL", 50.7, 53.6, 3.3, 7.3),
+    ("NO", 57.9, 80.9, -9.1, 33.6),
+    ("NP", 26.3, 30.5, 80.0, 88.3),
+    ("NR", -0.6, -0.5, 166.9, 167.0),
+    ("NU", -19.2, -18.9, -170.0, -169.7),
+    ("NZ", -52.7, -29.2, 165.8, -176.1),
+    ("OM", 16.6, 26.4, 52.0, 59.9),
+    ("PA", 7.2, 9.7, -83.1, -77.2),
+    ("PE", -18.4, 0.0, -81.4, -68.7),
+    ("PF", -27.7, -7.8, -154.7, -134.5),
+    ("PG", -11.7, -0.8, 140.8, 159.5),
+    ("PH", 4.6, 21.2, 114.0, 126.6),
+    ("PK", 23.6, 37.1, 60.9, 77.8),
+    ("PL", 49.0, 54.9, 14.1, 24.2),
+    ("PM", 46.7, 47.2, -56.5, -56.1),
+    ("PN", -25.1, -23.9, -130.8, -124.8),
+    ("PR", 17.8, 18.6, -67.9, -65.2),
+    ("PS", 31.2, 32.6, 34.2, 35.6),
+    ("PT", 30.0, 42.2, -31.3, -6.2),
+    ("PW", 2.8, 8.2, 131.1, 134.8),
+    ("PY", -27.6, -19.3, -62.7, -54.3),
+    ("QA", 24.5, 26.2, 50.7, 51.7),
+    ("RE", -21.4, -20.9, 55.2, 55.9),
+    ("RO", 43.6, 48.3, 20.3, 29.7),
+    ("RS", 42.2, 46.2, 18.8, 23.0),
+    ("RU", 41.2, 81.9, 19.6, -169.0),
+    ("RW", -2.9, -1.0, 28.8, 30.9),
+    ("SA", 16.3, 32.2, 34.5, 55.7),
+    ("SB", -12.3, -6.6, 155.5, 170.2),
+    ("SC", -10.3, -3.7, 46.2, 56.3),
+    ("SD", 8.6, 22.3, 21.8, 38.6),
+    ("SE", 55.3, 69.1, 11.0, 24.2),
+    ("SG", 1.2, 1.5, 103.6, 104.1),
+    ("SH", -40.4, -7.8, -14.5, -5.6),
+    ("SI", 45.4, 46.9, 13.4, 16.6),
+    ("SJ", 70.8, 80.9, -9.1, 33.6),
+    ("SK", 47.7, 49.7, 16.8, 22.6),
+    ("SL", 6.9, 10.0, -13.3, -10.3),
+    ("SM", 43.9, 44.0, 12.4, 12.5),
+    ("SN", 12.3, 16.7, -17.6, -11.3),
+    ("SO", -1.7, 12.0, 40.9, 51.5),
+    ("SR", 1.8, 6.1, -58.1, -53.9),
+    ("SS", 3.4, 12.3, 23.4, 36.0),
+    ("ST", -0.1, 1.8, 6.4, 7.5),
+    ("SV", 13.1, 14.5, -90.2, -87.6),
+    ("SX", 18.0, 18.1, -63.2, -63.0),
+    ("SY", 32.3, 37.4, 35.7, 42.4),
+    ("SZ", -27.4, -25.7, 30.7, 32.2),
+    ("TC", 21.0, 22.0, -72.5, -71.1),
+    ("TD", 7.4, 23.5, 13.4, 24.0),
+    ("TF", -50.0, -11.5, 39.6, 77.6),
+    ("TG", 6.1, 11.2, -0.2, 1.9),
+    ("TH", 5.6, 20.5, 97.3, 105.7),
+    ("TJ", 36.7, 41.1, 67.3, 75.2),
+    ("TK", -9.5, -8.5, -172.6, -171.1),
+    ("TL", -9.6, -8.1, 124.0, 127.4),
+    ("TM", 35.1, 42.8, 52.4, 66.8),
+    ("TN", 30.2, 37.6, 7.5, 11.7),
+    ("TO", -22.4, -15.5, -176.3, -173.7),
+    ("TR", 35.8, 42.2, 25.6, 44.9),
+    ("TT", 10.0, 11.4, -61.9, -60.5),
+    ("TV", -10.9, -5.6, 176.0, 179.9),
+    ("TW", 10.3, 26.4, 114.3, 122.1),
+    ("TZ", -11.8, -0.9, 29.3, 40.5),
+    ("UA", 44.3, 52.4, 22.1, 40.3),
+    ("UG", -1.5, 4.3, 29.5, 35.1),
+    ("UM", -0.5, 28.5, 166.5, -74.9),
+    ("US", 18.9, 71.4, 172.4, -66.9),
+    ("UY", -35.0, -30.1, -58.5, -53.1),
+    ("UZ", 37.2, 45.6, 56.0, 73.2),
+    ("VA", 41.9, 41.9, 12.4, 12.5),
+    ("VC", 12.5, 13.4, -61.5, -61.1),
+    ("VE", 0.6, 15.7, -73.4, -59.8),
+    ("VG", 18.3, 18.8, -64.9, -64.3),
+    ("VI", 17.6, 18.4, -65.1, -64.5),
+    ("VN", 7.0, 23.4, 102.1, 114.4),
+    ("VU", -20.3, -13.0, 166.5, 170.3),
+    ("WF", -14.4, -13.2, -178.2, -176.1),
+    ("WS", -14.1, -13.4, -172.8, -171.4),
+    ("XK", 41.8, 43.3, 20.0, 21.8),
+    ("YE", 12.1, 19.0, 42.5, 54.6),
+    ("YT", -13.1, -12.6, 45.0, 45.3),
+    ("ZA", -47.0, -22.1, 16.4, 38.0),
+    ("ZM", -18.1, -8.2, 21.9, 33.8),
+    ("ZW", -22.5, -15.6, 25.2, 33.1),
+];
@@ -0,0 +1,68 @@
+use crate::{Airport, Country, Region};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// A country together with counts computed from the other datasets
+#[derive(Serialize)]
+pub struct CountryInfo<'a> {
+    #[serde(flatten)]
+    pub country: &'a Country,
+    /// Number of airports in the country. Only present if the airport data was supplied.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub airport_count: Option<usize>,
+    /// Number of airports in the country with scheduled airline service. Only present if the airport data was supplied.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scheduled_service_count: Option<usize>,
+    /// Number of regions in the country. Only present if the region data was supplied.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub region_count: Option<usize>,
+}
+
+impl<'a> CountryInfo<'a> {
+    /// Computes the counts for a country from the airport and region data, if given
+    pub fn new(
+        country: &'a Country,
+        airports: Option<&[Airport]>,
+        regions: Option<&[Region]>,
+    ) -> Self {
+        let country_airports = airports.map(|airports| {
+            airports
+                .iter()
+                .filter(|airport| airport.iso_country == country.code)
+                .collect::<Vec<_>>()
+        });
+        CountryInfo {
+            country,
+            airport_count: country_airports.as_ref().map(Vec::len),
+            scheduled_service_count: country_airports.as_ref().map(|airports| {
+                airports
+                    .iter()
+                    .filter(|airport| airport.scheduled_service == Some(true))
+                    .count()
+            }),
+            region_count: regions.map(|regions| {
+                regions
+                    .iter()
+                    .filter(|region| region.iso_country == country.code && !region.is_unassigned())
+                    .count()
+            }),
+        }
+    }
+}
+
+/// Counts the airports in each region, by the `iso_region` of the airport, sorted by region code.
+/// If the region data is given, its regions without any airports are included with a count of 0.
+pub fn airports_per_region(
+    airports: &[Airport],
+    regions: Option<&[Region]>,
+) -> BTreeMap<String, usize> {
+    let mut counts: BTreeMap<String, usize> = regions
+        .unwrap_or_default()
+        .iter()
+        .map(|region| (region.code.clone(), 0))
+        .collect();
+    for airport in airports {
+        *counts.entry(airport.iso_region.clone()).or_default() += 1;
+    }
+    counts
+}
@@ -0,0 +1,152 @@
+use crate::{Airport, AirportFrequency, Country, Navaid, Region, Runway};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// All of the datasets, indexed for looking up related records
+///
+/// Serializes to a single document with one array per dataset.
+#[derive(Serialize)]
+pub struct OurAirportsData {
+    airports: Vec<Airport>,
+    runways: Vec<Runway>,
+    frequencies: Vec<AirportFrequency>,
+    navaids: Vec<Navaid>,
+    countries: Vec<Country>,
+    regions: Vec<Region>,
+    // The indexes are only for lookups, and never serialized, so their order can't change the output
+    #[serde(skip)]
+    airport_by_id: HashMap<String, usize>,
+    #[serde(skip)]
+    airport_by_ident: HashMap<String, usize>,
+    #[serde(skip)]
+    country_by_code: HashMap<String, usize>,
+    #[serde(skip)]
+    region_by_code: HashMap<String, usize>,
+    #[serde(skip)]
+    runways_by_airport: HashMap<String, Vec<usize>>,
+    #[serde(skip)]
+    frequencies_by_airport: HashMap<String, Vec<usize>>,
+    #[serde(skip)]
+    airports_by_country: HashMap<String, Vec<usize>>,
+}
+
+/// Maps the key of each record to its index
+fn index_by<T>(records: &[T], key: impl Fn(&T) -> &str) -> HashMap<String, usize> {
+    records
+        .iter()
+        .enumerate()
+        .map(|(i, record)| (key(record).to_string(), i))
+        .collect()
+}
+
+/// Maps each key to the indices of all records with that key
+fn group_by<T>(records: &[T], key: impl Fn(&T) -> &str) -> HashMap<String, Vec<usize>> {
+    let mut groups: HashMap<String, Vec<usize>> = HashMap::new();
+    for (i, record) in records.iter().enumerate() {
+        groups.entry(key(record).to_string()).or_default().push(i);
+    }
+    groups
+}
+
+/// Returns the records at the given indices, if any
+fn records_at<'a, T>(records: &'a [T], indices: Option<&Vec<usize>>) -> Vec<&'a T> {
+    indices
+        .map(|indices| indices.iter().map(|&i| &records[i]).collect())
+        .unwrap_or_default()
+}
+
+impl OurAirportsData {
+    /// Builds the indexes over the datasets
+    pub fn new(
+        airports: Vec<Airport>,
+        runways: Vec<Runway>,
+        frequencies: Vec<AirportFrequency>,
+        navaids: Vec<Navaid>,
+        countries: Vec<Country>,
+        regions: Vec<Region>,
+    ) -> Self {
+        OurAirportsData {
+            airport_by_id: index_by(&airports, |airport| &airport.id),
+            airport_by_ident: index_by(&airports, |airport| &airport.ident),
+            country_by_code: index_by(&countries, |country| &country.code),
+            region_by_code: index_by(&regions, |region| &region.code),
+            runways_by_airport: group_by(&runways, |runway| &runway.airport_ref),
+            frequencies_by_airport: group_by(&frequencies, |frequency| &frequency.airport_ref),
+            airports_by_country: group_by(&airports, |airport| &airport.iso_country),
+            airports,
+            runways,
+            frequencies,
+            navaids,
+            countries,
+            regions,
+        }
+    }
+
+    /// All of the airports
+    pub fn airports(&self) -> &[Airport] {
+        &self.airports
+    }
+
+    /// All of the runways
+    pub fn runways(&self) -> &[Runway] {
+        &self.runways
+    }
+
+    /// All of the airport frequencies
+    pub fn frequencies(&self) -> &[AirportFrequency] {
+        &self.frequencies
+    }
+
+    /// All of the navaids
+    pub fn navaids(&self) -> &[Navaid] {
+        &self.navaids
+    }
+
+    /// All of the countries
+    pub fn countries(&self) -> &[Country] {
+        &self.countries
+    }
+
+    /// All of the regions
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    /// Returns the airport with the given `id`
+    pub fn airport(&self, id: &str) -> Option<&Airport> {
+        self.airport_by_id.get(id).map(|&i| &self.airports[i])
+    }
+
+    /// Returns the airport with the given `ident`
+    pub fn airport_by_ident(&self, ident: &str) -> Option<&Airport> {
+        self.airport_by_ident.get(ident).map(|&i| &self.airports[i])
+    }
+
+    /// Returns the country with the given `code`
+    pub fn country(&self, code: &str) -> Option<&Country> {
+        self.country_by_code.get(code).map(|&i| &self.countries[i])
+    }
+
+    /// Returns the region with the given `code`
+    pub fn region(&self, code: &str) -> Option<&Region> {
+        self.region_by_code.get(code).map(|&i| &self.regions[i])
+    }
+
+    /// Returns the runways of the airport with the given `id`
+    pub fn runways_for(&self, airport_id: &str) -> Vec<&Runway> {
+        records_at(&self.runways, self.runways_by_airport.get(airport_id))
+    }
+
+    /// Returns the frequencies of the airport with the given `id`
+    pub fn frequencies_for(&self, airport_id: &str) -> Vec<&AirportFrequency> {
+        records_at(
+            &self.frequencies,
+            self.frequencies_by_airport.get(airport_id),
+        )
+    }
+
+    /// Returns the airports in the country with the given `code`
+    pub fn airports_in_country(&self, code: &str) -> Vec<&Airport> {
+        records_at(&self.airports, self.airports_by_country.get(code))
+    }
+}
@@ -0,0 +1,37 @@
+use crate::AirportFrequency;
+use std::collections::HashMap;
+
+/// Merges the frequencies with the same `airport_ident`, `frequency_type` and `frequency_mhz`,
+/// keeping the one with the longest description in place of the first of them.
+/// `frequency` returns the frequency of each record, so that records paired with other data can be merged.
+/// Returns the remaining records and how many were merged into them.
+pub fn dedupe_frequencies<T>(
+    records: Vec<T>,
+    frequency: impl Fn(&T) -> &AirportFrequency,
+) -> (Vec<T>, usize) {
+    let mut kept: Vec<T> = Vec::with_capacity(records.len());
+    let mut positions: HashMap<(String, String, String), usize> = HashMap::new();
+    let mut merged_count = 0;
+    for record in records {
+        let current = frequency(&record);
+        let key = (
+            current.airport_ident.clone(),
+            current.frequency_type.clone(),
+            current.frequency_mhz.clone(),
+        );
+        let description_len = current.description.len();
+        match positions.get(&key) {
+            Some(&position) => {
+                merged_count += 1;
+                if description_len > frequency(&kept[position]).description.len() {
+                    kept[position] = record;
+                }
+            }
+            None => {
+                positions.insert(key, kept.len());
+                kept.push(record);
+            }
+        }
+    }
+    (kept, merged_count)
+}
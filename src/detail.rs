@@ -0,0 +1,80 @@
+use crate::{distance_nm, Airport, AirportFrequency, Navaid, OurAirportsData, Runway};
+use serde::Serialize;
+
+/// A navaid near an airport, with its distance from the airport
+#[derive(Serialize)]
+pub struct NearbyNavaid<'a> {
+    /// Great-circle distance from the airport, in nautical miles.
+    pub distance_nm: f64,
+    /// The navaid.
+    pub navaid: &'a Navaid,
+}
+
+/// Everything known about an airport, joined from all of the datasets
+#[derive(Serialize)]
+pub struct AirportDetail<'a> {
+    /// The airport.
+    pub airport: &'a Airport,
+    /// The name of the airport's country. None if the country is unknown.
+    pub country_name: Option<&'a str>,
+    /// The name of the airport's region. None if the region is unknown.
+    pub region_name: Option<&'a str>,
+    /// The runways of the airport.
+    pub runways: Vec<&'a Runway>,
+    /// The frequencies of the airport.
+    pub frequencies: Vec<&'a AirportFrequency>,
+    /// The navaids within the radius of the airport, nearest first.
+    /// Empty if the airport has no coordinates.
+    pub nearby_navaids: Vec<NearbyNavaid<'a>>,
+}
+
+impl OurAirportsData {
+    /// Returns the airport with the given ICAO code, ignoring case: the airport with that `ident`,
+    /// or else the first airport whose `Airport::guess_icao_code` is the code
+    pub fn airport_by_icao(&self, icao: &str) -> Option<&Airport> {
+        self.airport_by_ident(&icao.to_ascii_uppercase())
+            .or_else(|| {
+                self.airports()
+                    .iter()
+                    .find(|airport| airport.guess_icao_code().eq_ignore_ascii_case(icao))
+            })
+    }
+
+    /// Assembles everything known about an airport, with the navaids within `radius_nm` nautical miles of it
+    pub fn airport_detail<'a>(&'a self, airport: &'a Airport, radius_nm: f64) -> AirportDetail<'a> {
+        let mut nearby_navaids: Vec<NearbyNavaid> =
+            match (airport.latitude_deg, airport.longitude_deg) {
+                (Some(latitude), Some(longitude)) => self
+                    .navaids()
+                    .iter()
+                    .filter_map(|navaid| {
+                        let distance_nm = distance_nm(
+                            latitude,
+                            longitude,
+                            navaid.latitude_deg?,
+                            navaid.longitude_deg?,
+                        );
+                        (distance_nm <= radius_nm).then_some(NearbyNavaid {
+                            distance_nm,
+                            navaid,
+                        })
+                    })
+                    .collect(),
+                _ => Vec::new(),
+            };
+        nearby_navaids.sort_by(|a, b| a.distance_nm.partial_cmp(&b.distance_nm).unwrap());
+
+        AirportDetail {
+            airport,
+            country_name: self
+                .country(&airport.iso_country)
+                .map(|country| country.name.as_str()),
+            region_name: self
+                .region(&airport.iso_region)
+                .map(|region| region.name.as_str()),
+            runways: self.runways_for(&airport.id),
+            frequencies: self.frequencies_for(&airport.id),
+            nearby_navaids,
+        }
+    }
+}
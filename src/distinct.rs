@@ -0,0 +1,49 @@
+use crate::error::Result;
+use crate::Error;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// A distinct value of a field, and how many records have it
+#[derive(Serialize)]
+pub struct ValueCount {
+    pub value: String,
+    pub count: usize,
+}
+
+/// Counts the distinct values of a field of records serialized as JSON objects, sorted by count, most common first,
+/// and then by value. Each element of an array (such as `keywords`) is counted on its own, and null counts as "".
+pub fn count_distinct(
+    records: impl IntoIterator<Item = Value>,
+    field: &str,
+) -> Result<Vec<ValueCount>> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for record in records {
+        let values = match record.get(field) {
+            Some(Value::Array(values)) => values.clone(),
+            Some(value) => vec![value.clone()],
+            None => return Err(Error::Field(format!("No field named {}", field))),
+        };
+        for value in values {
+            let value = match value {
+                Value::String(value) => value,
+                Value::Null => String::new(),
+                Value::Number(_) | Value::Bool(_) => value.to_string(),
+                _ => {
+                    return Err(Error::Field(format!(
+                        "Can not count the values of field {}",
+                        field
+                    )))
+                }
+            };
+            *counts.entry(value).or_default() += 1;
+        }
+    }
+
+    let mut value_counts: Vec<ValueCount> = counts
+        .into_iter()
+        .map(|(value, count)| ValueCount { value, count })
+        .collect();
+    value_counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+    Ok(value_counts)
+}
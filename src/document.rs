@@ -0,0 +1,38 @@
+use bson::{Bson, Document};
+use serde_json::{Map, Value};
+
+/// The fields holding the integer identifiers of records, which are strings in the csv data
+const ID_FIELDS: [&str; 2] = ["id", "airport_ref"];
+
+/// Converts a JSON value to BSON, with integers as int64 and other numbers as doubles
+fn to_bson(value: Value) -> Bson {
+    match value {
+        Value::Null => Bson::Null,
+        Value::Bool(value) => Bson::Boolean(value),
+        Value::Number(number) => match number.as_i64() {
+            Some(number) => Bson::Int64(number),
+            None => Bson::Double(number.as_f64().unwrap_or(f64::NAN)),
+        },
+        Value::String(value) => Bson::String(value),
+        Value::Array(values) => Bson::Array(values.into_iter().map(to_bson).collect()),
+        Value::Object(fields) => Bson::Document(to_document(fields)),
+    }
+}
+
+/// Converts a record serialized as a JSON object to a BSON document, in the same field order.
+/// The integer identifiers `id` and `airport_ref` are written as int64 instead of strings.
+pub fn to_document(record: Map<String, Value>) -> Document {
+    record
+        .into_iter()
+        .map(|(key, value)| {
+            let value = match (ID_FIELDS.contains(&key.as_str()), value) {
+                (true, Value::String(id)) => match id.parse::<i64>() {
+                    Ok(id) => Bson::Int64(id),
+                    Err(_) => Bson::String(id),
+                },
+                (_, value) => to_bson(value),
+            };
+            (key, value)
+        })
+        .collect()
+}
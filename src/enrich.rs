@@ -0,0 +1,194 @@
+use crate::{Airport, Country, Navaid, NavaidType, Region, Runway};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+
+/// Maps the code of each country to its continent
+pub fn country_continents(countries: &[Country]) -> HashMap<String, String> {
+    countries
+        .iter()
+        .map(|country| (country.code.clone(), country.continent.clone()))
+        .collect()
+}
+
+/// Maps the code of each country to its name
+pub fn country_names(countries: &[Country]) -> HashMap<String, String> {
+    countries
+        .iter()
+        .map(|country| (country.code.clone(), country.name.clone()))
+        .collect()
+}
+
+/// Maps the code of each region to its name
+pub fn region_names(regions: &[Region]) -> HashMap<String, String> {
+    regions
+        .iter()
+        .map(|region| (region.code.clone(), region.name.clone()))
+        .collect()
+}
+
+/// Groups the regions by the code of their country, leaving out the pseudo regions "U-A" of unassigned airports,
+/// in the order of the region data
+pub fn regions_by_country(regions: Vec<Region>) -> HashMap<String, Vec<Region>> {
+    let mut grouped: HashMap<String, Vec<Region>> = HashMap::new();
+    for region in regions {
+        if !region.is_unassigned() {
+            grouped
+                .entry(region.iso_country.clone())
+                .or_default()
+                .push(region);
+        }
+    }
+    grouped
+}
+
+/// The regions of a country, as their codes or as the whole regions
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum CountryRegions {
+    Codes(Vec<String>),
+    Full(Vec<Region>),
+}
+
+impl Country {
+    /// Adds the `regions` field, from the regions with the country's code in `regions_by_country`:
+    /// their codes, or the whole regions if `full`. A country without any regions gets an empty array.
+    pub fn add_regions(&mut self, regions_by_country: &HashMap<String, Vec<Region>>, full: bool) {
+        let regions = regions_by_country
+            .get(&self.code)
+            .map_or(&[][..], Vec::as_slice);
+        self.regions = Some(if full {
+            CountryRegions::Full(regions.to_vec())
+        } else {
+            CountryRegions::Codes(regions.iter().map(|region| region.code.clone()).collect())
+        });
+    }
+}
+
+/// Joins each runway with its airport on `airport_ref`, as one flat record per runway:
+/// the fields of the runway, with the `name`, `iso_country`, `latitude_deg` and `longitude_deg` of the airport
+/// added after `airport_ident` as `airport_name` and so on. They are null if no airport has the runway's `airport_ref`.
+pub fn runways_with_airports(
+    runways: &[Runway],
+    airports: &[Airport],
+) -> serde_json::Result<Vec<Value>> {
+    let airports_by_id: HashMap<&str, &Airport> = airports
+        .iter()
+        .map(|airport| (airport.id.as_str(), airport))
+        .collect();
+    runways
+        .iter()
+        .map(|runway| {
+            let airport = airports_by_id.get(runway.airport_ref.as_str());
+            let mut merged = Map::new();
+            if let Value::Object(fields) = serde_json::to_value(runway)? {
+                for (key, value) in fields {
+                    let is_airport_ident = key == "airport_ident";
+                    merged.insert(key, value);
+                    if is_airport_ident {
+                        merged.insert(
+                            "airport_name".to_string(),
+                            json!(airport.map(|airport| &airport.name)),
+                        );
+                        merged.insert(
+                            "airport_iso_country".to_string(),
+                            json!(airport.map(|airport| &airport.iso_country)),
+                        );
+                        merged.insert(
+                            "airport_latitude_deg".to_string(),
+                            json!(airport.and_then(|airport| airport.latitude_deg)),
+                        );
+                        merged.insert(
+                            "airport_longitude_deg".to_string(),
+                            json!(airport.and_then(|airport| airport.longitude_deg)),
+                        );
+                    }
+                }
+            }
+            Ok(Value::Object(merged))
+        })
+        .collect()
+}
+
+impl Airport {
+    /// Fills in a blank `continent` with the continent of the airport's country, if known.
+    /// Returns whether the continent was filled in.
+    pub fn fill_continent(&mut self, country_continents: &HashMap<String, String>) -> bool {
+        if !self.continent.is_empty() {
+            return false;
+        }
+        match country_continents.get(&self.iso_country) {
+            Some(continent) if !continent.is_empty() => {
+                self.continent = continent.clone();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Adds the `country_name` and `region_name` fields, from the names of the airport's country and region.
+    /// A field is set to null if its code isn't in the map.
+    pub fn add_names(
+        &mut self,
+        country_names: &HashMap<String, String>,
+        region_names: &HashMap<String, String>,
+    ) {
+        self.country_name = Some(country_names.get(&self.iso_country).cloned());
+        self.region_name = Some(region_names.get(&self.iso_region).cloned());
+    }
+
+    /// Returns the ICAO code of the airport, as a guess:
+    /// `gps_code` if it looks like an ICAO code (four ASCII letters), otherwise `ident`.
+    pub fn guess_icao_code(&self) -> &str {
+        if looks_like_icao(&self.gps_code) {
+            &self.gps_code
+        } else {
+            &self.ident
+        }
+    }
+
+    /// Adds the `icao_code` alias field, as a clearer name for the ICAO code, from `Airport::guess_icao_code`.
+    pub fn add_icao_code(&mut self) {
+        self.icao_code = Some(self.guess_icao_code().to_string());
+    }
+}
+
+/// Returns whether a code looks like an ICAO code: four ASCII letters
+pub(crate) fn looks_like_icao(code: &str) -> bool {
+    code.len() == 4 && code.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+/// The radio band a navaid transmits on
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum NavaidBand {
+    /// Low and medium frequencies, 30 kHz to 3 MHz, used by NDBs.
+    #[serde(rename = "LF/MF")]
+    LfMf,
+    /// Very high frequencies, 30 to 300 MHz, used by VORs.
+    #[serde(rename = "VHF")]
+    Vhf,
+    /// Ultra high frequencies, 300 MHz to 3 GHz, used by DMEs and TACANs, which are tuned with a paired VHF frequency.
+    #[serde(rename = "UHF")]
+    Uhf,
+}
+
+/// Returns the radio band of a navaid from the name of its type, from `NavaidType::band`.
+/// Navaids of other types are classified by `frequency_khz`, and are None if it isn't a number in one of the bands.
+pub fn navaid_band(navaid_type: &str, frequency_khz: &str) -> Option<NavaidBand> {
+    match navaid_type.parse::<NavaidType>() {
+        Ok(navaid_type) => Some(navaid_type.band()),
+        Err(_) => match frequency_khz.trim().parse::<f64>().ok()? {
+            khz if (30.0..3_000.0).contains(&khz) => Some(NavaidBand::LfMf),
+            khz if (30_000.0..300_000.0).contains(&khz) => Some(NavaidBand::Vhf),
+            khz if (300_000.0..3_000_000.0).contains(&khz) => Some(NavaidBand::Uhf),
+            _ => None,
+        },
+    }
+}
+
+impl Navaid {
+    /// Adds the `band` field, from `navaid_band`
+    pub fn add_band(&mut self) {
+        self.band = Some(Some(self.navaid_type.band()));
+    }
+}
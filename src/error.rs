@@ -0,0 +1,91 @@
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// The class of failure that occurred while reading OurAirports data.
+#[derive(Debug)]
+pub enum Error {
+    /// The data could not be downloaded from the URL.
+    Network(String, reqwest::Error),
+    /// The input file could not be found or read.
+    File(PathBuf, io::Error),
+    /// The output could not be written.
+    Io(io::Error),
+    /// The csv data could not be read, or a record could not be written as csv.
+    Parse(csv::Error),
+    /// A record could not be parsed into its type, with the line of the data it starts on.
+    Deserialize { line: u64, source: csv::Error },
+    /// The data doesn't have a column of its type, with the columns it has.
+    MissingColumn(String, Vec<String>),
+    /// A record could not be converted to or from JSON.
+    Json(serde_json::Error),
+    /// A field given by name can't be used, because the records don't have it or because of its values.
+    Field(String),
+    /// The data was parsed, but did not pass validation.
+    Validation(String),
+}
+
+/// The result of the fallible functions of the library
+pub(crate) type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Network(url, _) => write!(f, "Could not open page: {}", url),
+            Error::File(path, _) => write!(f, "Could not open file: {}", path.to_string_lossy()),
+            Error::Io(_) => write!(f, "Could not write output"),
+            Error::Parse(_) => write!(f, "Could not parse data"),
+            Error::Deserialize { line, .. } => {
+                write!(f, "Could not parse the record on line {}", line)
+            }
+            Error::MissingColumn(column, found) => write!(
+                f,
+                "Expected column `{}` not found; got [{}]",
+                column,
+                found.join(", ")
+            ),
+            Error::Json(_) => write!(f, "Could not convert a record to JSON"),
+            Error::Field(message) => write!(f, "{}", message),
+            Error::Validation(message) => write!(f, "Validation failed: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Network(_, err) => Some(err),
+            Error::File(_, err) => Some(err),
+            Error::Io(err) => Some(err),
+            Error::Parse(err) => Some(err),
+            Error::Deserialize { source, .. } => Some(source),
+            Error::Json(err) => Some(err),
+            Error::MissingColumn(..) | Error::Field(_) | Error::Validation(_) => None,
+        }
+    }
+}
+
+impl From<csv::Error> for Error {
+    /// A record that fails to deserialize is a `Deserialize` error of its line, and anything else a `Parse` error
+    fn from(err: csv::Error) -> Self {
+        match err.kind() {
+            csv::ErrorKind::Deserialize { pos: Some(pos), .. } => Error::Deserialize {
+                line: pos.line(),
+                source: err,
+            },
+            _ => Error::Parse(err),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Json(err)
+    }
+}
@@ -0,0 +1,137 @@
+use crate::error::{Error, Result};
+use crate::geo::point_coordinates;
+use flatgeobuf::geozero::error::Result as GeozeroResult;
+use flatgeobuf::geozero::{ColumnValue, GeomProcessor, PropertyProcessor};
+use flatgeobuf::{ColumnType, FgbCrs, FgbWriter, FgbWriterOptions, GeometryType, GeozeroGeometry};
+use serde_json::Value;
+use std::io;
+
+/// The location of a feature, in longitude and latitude
+struct Point([f64; 2]);
+
+impl GeozeroGeometry for Point {
+    fn process_geom<P: GeomProcessor>(&self, processor: &mut P) -> GeozeroResult<()> {
+        processor.point_begin(0)?;
+        processor.xy(self.0[0], self.0[1], 0)?;
+        processor.point_end(0)
+    }
+}
+
+/// Returns the FlatGeobuf `ColumnType` of a field from its values, as for the types of `to_sql`,
+/// with arrays and objects (such as `keywords`) as JSON. Nulls are ignored.
+fn column_type<'a>(values: impl Iterator<Item = &'a Value>) -> ColumnType {
+    let mut column_type = None;
+    for value in values {
+        let value_type = match value {
+            Value::Null => continue,
+            Value::Number(number) if number.is_i64() => ColumnType::Long,
+            Value::Number(_) => ColumnType::Double,
+            Value::Bool(_) => ColumnType::Bool,
+            Value::String(_) => ColumnType::String,
+            Value::Array(_) | Value::Object(_) => ColumnType::Json,
+        };
+        column_type = match (column_type, value_type) {
+            (None, value_type) => Some(value_type),
+            (Some(ColumnType::Long), ColumnType::Double)
+            | (Some(ColumnType::Double), ColumnType::Long) => Some(ColumnType::Double),
+            (Some(column_type), value_type) if column_type == value_type => Some(column_type),
+            _ => Some(ColumnType::String),
+        };
+    }
+    column_type.unwrap_or(ColumnType::String)
+}
+
+/// Writes the non-null fields of a record as the properties of its feature
+fn write_properties(
+    feature: &mut impl PropertyProcessor,
+    record: &Value,
+    columns: &[(String, ColumnType)],
+) -> GeozeroResult<()> {
+    for (i, (name, column_type)) in columns.iter().enumerate() {
+        let value = match record.get(name) {
+            None | Some(Value::Null) => continue,
+            Some(value) => value,
+        };
+        let text;
+        let column_value = match *column_type {
+            ColumnType::Bool => ColumnValue::Bool(value.as_bool().unwrap_or_default()),
+            ColumnType::Long => ColumnValue::Long(value.as_i64().unwrap_or_default()),
+            ColumnType::Double => ColumnValue::Double(value.as_f64().unwrap_or_default()),
+            ColumnType::String if value.is_string() => {
+                ColumnValue::String(value.as_str().unwrap_or_default())
+            }
+            column_type => {
+                text = value.to_string();
+                if column_type == ColumnType::Json {
+                    ColumnValue::Json(&text)
+                } else {
+                    ColumnValue::String(&text)
+                }
+            }
+        };
+        feature.property(i, name, &column_value)?;
+    }
+    Ok(())
+}
+
+/// Returns the error of the writer as an `Io` error, as it fails when its output can't be written
+fn write_error(err: impl std::error::Error + Send + Sync + 'static) -> Error {
+    Error::Io(io::Error::other(err))
+}
+
+/// Converts records serialized as JSON into a FlatGeobuf file (version 3 of the format) of points named `name`,
+/// located by their `latitude_deg` and `longitude_deg` fields in WGS 84, with the fields of the records as properties.
+/// Records without coordinates are left out. The features are ordered along a Hilbert curve and a spatial index
+/// is written, so that readers such as GDAL and QGIS can query a bounding box without reading the whole file.
+pub fn to_flatgeobuf(name: &str, records: &[Value]) -> Result<Vec<u8>> {
+    let features: Vec<([f64; 2], &Value)> = records
+        .iter()
+        .filter_map(|record| Some((point_coordinates(record)?, record)))
+        .collect();
+
+    let mut column_names: Vec<&String> = Vec::new();
+    for (_, record) in &features {
+        if let Value::Object(fields) = record {
+            for key in fields.keys() {
+                if !column_names.contains(&key) {
+                    column_names.push(key);
+                }
+            }
+        }
+    }
+    let columns: Vec<(String, ColumnType)> = column_names
+        .into_iter()
+        .map(|name| {
+            let values = features.iter().filter_map(|(_, record)| record.get(name));
+            (name.clone(), column_type(values))
+        })
+        .collect();
+
+    let options = FgbWriterOptions {
+        // an empty file has no index, as there is nothing to index
+        write_index: !features.is_empty(),
+        crs: FgbCrs {
+            code: 4326,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut writer =
+        FgbWriter::create_with_options(name, GeometryType::Point, options).map_err(write_error)?;
+    for (name, column_type) in &columns {
+        writer.add_column(name, *column_type, |_, _| {});
+    }
+    for (point, record) in features {
+        let mut properties = Ok(());
+        writer
+            .add_feature_geom(Point(point), |feature| {
+                properties = write_properties(feature, record, &columns);
+            })
+            .map_err(write_error)?;
+        properties.map_err(write_error)?;
+    }
+
+    let mut bytes = Vec::new();
+    writer.write(&mut bytes).map_err(write_error)?;
+    Ok(bytes)
+}
@@ -0,0 +1,92 @@
+use crate::error::Result;
+use crate::table::into_string;
+use serde::Serialize;
+
+/// Columns of headings in degrees, normalized to [0, 360)
+const HEADING_COLUMNS: [&str; 2] = ["le_heading_degT", "he_heading_degT"];
+
+/// Columns of codes, which are always upper case
+const CODE_COLUMNS: [&str; 9] = [
+    "ident",
+    "gps_code",
+    "iata_code",
+    "code",
+    "continent",
+    "iso_country",
+    "iso_region",
+    "airport_ident",
+    "associated_airport",
+];
+
+/// Columns of booleans, with the value a blank one is set to
+const BOOLEAN_COLUMNS: [(&str, &str); 3] = [
+    ("scheduled_service", "no"),
+    ("lighted", "0"),
+    ("closed", "0"),
+];
+
+/// A correction made to a field of the data
+#[derive(Serialize)]
+pub struct Fix {
+    /// The line of the csv data the record starts on
+    pub line: u64,
+    /// The column of the field
+    pub field: String,
+    /// The field as it was
+    pub before: String,
+    /// The field after the correction
+    pub after: String,
+}
+
+/// Returns the field with the safe corrections for its column applied
+fn fix_field(column: &str, value: &str) -> String {
+    let value = value.trim();
+    if HEADING_COLUMNS.contains(&column) {
+        match value.parse::<f64>() {
+            Ok(heading) if !(0.0..360.0).contains(&heading) => {
+                return heading.rem_euclid(360.0).to_string()
+            }
+            _ => {}
+        }
+    } else if CODE_COLUMNS.contains(&column) {
+        return value.to_uppercase();
+    } else if value.is_empty() {
+        if let Some((_, blank)) = BOOLEAN_COLUMNS.iter().find(|(name, _)| *name == column) {
+            return blank.to_string();
+        }
+    }
+    value.to_string()
+}
+
+/// Applies safe corrections to csv data of any type, returning the corrected data and the corrections made:
+/// trims the whitespace around every field, upper cases codes, normalizes runway headings to [0, 360)
+/// and sets blank booleans to false. Issues that can't be fixed safely, such as unknown codes, are left alone.
+pub fn fix_csv(data: &str) -> Result<(String, Vec<Fix>)> {
+    let mut rdr = csv::Reader::from_reader(data.as_bytes());
+    let mut wtr = csv::WriterBuilder::new()
+        .terminator(csv::Terminator::Any(b'\n'))
+        .from_writer(Vec::new());
+    let headers = rdr.headers()?.clone();
+    wtr.write_record(&headers)?;
+
+    let mut fixes = Vec::new();
+    for record in rdr.records() {
+        let record = record?;
+        let line = record.position().map_or(0, |position| position.line());
+        let mut row = Vec::with_capacity(record.len());
+        for (column, value) in headers.iter().zip(record.iter()) {
+            let fixed = fix_field(column, value);
+            if fixed != value {
+                fixes.push(Fix {
+                    line,
+                    field: column.to_string(),
+                    before: value.to_string(),
+                    after: fixed.clone(),
+                });
+            }
+            row.push(fixed);
+        }
+        wtr.write_record(&row)?;
+    }
+    Ok((into_string(wtr)?, fixes))
+}
@@ -0,0 +1,269 @@
+use serde::Serialize;
+use serde_json::ser::Formatter;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::io;
+
+/// Formats JSON with one line per record, like `jq -c` over an array.
+///
+/// Arrays and objects nested up to `record_depth` levels deep are indented with one element per line,
+/// and anything deeper, such as the fields of the records, is written on a single line.
+pub struct RecordLinesFormatter {
+    record_depth: usize,
+    depth: usize,
+    has_value: bool,
+}
+
+impl RecordLinesFormatter {
+    /// Creates a formatter for records nested `record_depth` levels deep,
+    /// e.g. 1 for an array of records
+    pub fn new(record_depth: usize) -> Self {
+        RecordLinesFormatter {
+            record_depth,
+            depth: 0,
+            has_value: false,
+        }
+    }
+
+    /// Returns whether the elements of the current array or object go on their own lines
+    fn breaks_lines(&self) -> bool {
+        self.depth <= self.record_depth
+    }
+}
+
+/// Writes a newline followed by the indentation for the given depth
+fn write_line<W: ?Sized + io::Write>(writer: &mut W, depth: usize) -> io::Result<()> {
+    writer.write_all(b"\n")?;
+    for _ in 0..depth {
+        writer.write_all(b"  ")?;
+    }
+    Ok(())
+}
+
+impl Formatter for RecordLinesFormatter {
+    fn begin_array<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.depth += 1;
+        self.has_value = false;
+        writer.write_all(b"[")
+    }
+
+    fn end_array<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        let breaks_lines = self.breaks_lines();
+        self.depth -= 1;
+        if breaks_lines && self.has_value {
+            write_line(writer, self.depth)?;
+        }
+        writer.write_all(b"]")
+    }
+
+    fn begin_array_value<W: ?Sized + io::Write>(
+        &mut self,
+        writer: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        if !first {
+            writer.write_all(b",")?;
+        }
+        if self.breaks_lines() {
+            write_line(writer, self.depth)?;
+        }
+        Ok(())
+    }
+
+    fn end_array_value<W: ?Sized + io::Write>(&mut self, _writer: &mut W) -> io::Result<()> {
+        self.has_value = true;
+        Ok(())
+    }
+
+    fn begin_object<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        self.depth += 1;
+        self.has_value = false;
+        writer.write_all(b"{")
+    }
+
+    fn end_object<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        let breaks_lines = self.breaks_lines();
+        self.depth -= 1;
+        if breaks_lines && self.has_value {
+            write_line(writer, self.depth)?;
+        }
+        writer.write_all(b"}")
+    }
+
+    fn begin_object_key<W: ?Sized + io::Write>(
+        &mut self,
+        writer: &mut W,
+        first: bool,
+    ) -> io::Result<()> {
+        self.begin_array_value(writer, first)
+    }
+
+    fn begin_object_value<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        if self.breaks_lines() {
+            writer.write_all(b": ")
+        } else {
+            writer.write_all(b":")
+        }
+    }
+
+    fn end_object_value<W: ?Sized + io::Write>(&mut self, _writer: &mut W) -> io::Result<()> {
+        self.has_value = true;
+        Ok(())
+    }
+}
+
+/// Implements the methods of `Formatter` for arrays and objects by calling the same methods of `self.inner`
+macro_rules! delegate_structure {
+    () => {
+        fn begin_array<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+            self.inner.begin_array(writer)
+        }
+
+        fn end_array<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+            self.inner.end_array(writer)
+        }
+
+        fn begin_array_value<W: ?Sized + io::Write>(
+            &mut self,
+            writer: &mut W,
+            first: bool,
+        ) -> io::Result<()> {
+            self.inner.begin_array_value(writer, first)
+        }
+
+        fn end_array_value<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+            self.inner.end_array_value(writer)
+        }
+
+        fn begin_object<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+            self.inner.begin_object(writer)
+        }
+
+        fn end_object<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+            self.inner.end_object(writer)
+        }
+
+        fn begin_object_key<W: ?Sized + io::Write>(
+            &mut self,
+            writer: &mut W,
+            first: bool,
+        ) -> io::Result<()> {
+            self.inner.begin_object_key(writer, first)
+        }
+
+        fn end_object_key<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+            self.inner.end_object_key(writer)
+        }
+
+        fn begin_object_value<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+            self.inner.begin_object_value(writer)
+        }
+
+        fn end_object_value<W: ?Sized + io::Write>(&mut self, writer: &mut W) -> io::Result<()> {
+            self.inner.end_object_value(writer)
+        }
+    };
+}
+
+/// Formats floats with a fixed number of decimals, as JSON numbers, and everything else as the inner formatter does
+pub struct FixedDecimalsFormatter<F> {
+    inner: F,
+    decimals: usize,
+}
+
+impl<F: Formatter> FixedDecimalsFormatter<F> {
+    /// Creates a formatter writing floats with the given number of decimals
+    pub fn new(inner: F, decimals: usize) -> Self {
+        FixedDecimalsFormatter { inner, decimals }
+    }
+}
+
+impl<F: Formatter> Formatter for FixedDecimalsFormatter<F> {
+    fn write_f32<W: ?Sized + io::Write>(&mut self, writer: &mut W, value: f32) -> io::Result<()> {
+        write!(writer, "{:.*}", self.decimals, value)
+    }
+
+    fn write_f64<W: ?Sized + io::Write>(&mut self, writer: &mut W, value: f64) -> io::Result<()> {
+        write!(writer, "{:.*}", self.decimals, value)
+    }
+
+    delegate_structure!();
+}
+
+/// Formats floats with the fewest digits that round trip after rounding to 15 significant digits,
+/// the precision of an f64, so that float noise such as `51.470000000000006` is written as `51.47`,
+/// and everything else as the inner formatter does
+pub struct CompactFloatsFormatter<F> {
+    inner: F,
+}
+
+impl<F: Formatter> CompactFloatsFormatter<F> {
+    /// Creates a formatter writing floats compactly
+    pub fn new(inner: F) -> Self {
+        CompactFloatsFormatter { inner }
+    }
+}
+
+/// Rounds a float to 15 significant digits, which leaves any decimal of up to 15 digits read from the data as it was.
+/// The digits are written to a buffer on the stack, so no float allocates.
+fn round_significant(value: f64) -> f64 {
+    use std::io::Write;
+    // the longest is like -1.23456789012345e-308
+    let mut digits = [0u8; 32];
+    let mut cursor = io::Cursor::new(&mut digits[..]);
+    if write!(cursor, "{:.14e}", value).is_err() {
+        return value;
+    }
+    let len = cursor.position() as usize;
+    std::str::from_utf8(&digits[..len])
+        .ok()
+        .and_then(|text| text.parse().ok())
+        .unwrap_or(value)
+}
+
+impl<F: Formatter> Formatter for CompactFloatsFormatter<F> {
+    fn write_f32<W: ?Sized + io::Write>(&mut self, writer: &mut W, value: f32) -> io::Result<()> {
+        self.inner.write_f32(writer, value)
+    }
+
+    fn write_f64<W: ?Sized + io::Write>(&mut self, writer: &mut W, value: f64) -> io::Result<()> {
+        self.inner.write_f64(writer, round_significant(value))
+    }
+
+    delegate_structure!();
+}
+
+/// Serializes a value to JSON with the given formatter.
+pub fn to_string_with_formatter<T, F>(value: &T, formatter: F) -> serde_json::Result<String>
+where
+    T: Serialize + ?Sized,
+    F: Formatter,
+{
+    let mut writer = Vec::new();
+    let mut serializer = serde_json::Serializer::with_formatter(&mut writer, formatter);
+    value.serialize(&mut serializer)?;
+    Ok(String::from_utf8(writer).expect("serde_json only writes valid UTF-8"))
+}
+
+/// Serializes a value to JSON with one line per record, for records nested `record_depth` levels deep.
+pub fn to_string_record_lines<T: Serialize + ?Sized>(
+    value: &T,
+    record_depth: usize,
+) -> serde_json::Result<String> {
+    to_string_with_formatter(value, RecordLinesFormatter::new(record_depth))
+}
+
+/// Sorts the keys of every object in the value alphabetically, at any depth
+pub fn sort_keys(value: Value) -> Value {
+    match value {
+        Value::Object(fields) => {
+            let sorted: BTreeMap<String, Value> = fields
+                .into_iter()
+                .map(|(key, value)| (key, sort_keys(value)))
+                .collect();
+            Value::Object(sorted.into_iter().collect::<Map<_, _>>())
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(sort_keys).collect()),
+        value => value,
+    }
+}
@@ -0,0 +1,294 @@
+use crate::Airport;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::f64::consts::PI;
+use std::fmt;
+use std::str::FromStr;
+
+/// A box of longitudes and latitudes, in decimal degrees
+///
+/// If `min_lon` is greater than `max_lon`, the box crosses the antimeridian (the 180° line),
+/// and contains the longitudes from `min_lon` to 180 and from -180 to `max_lon`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingBox {
+    pub min_lon: f64,
+    pub min_lat: f64,
+    pub max_lon: f64,
+    pub max_lat: f64,
+}
+
+impl BoundingBox {
+    /// Returns whether the box crosses the antimeridian
+    pub fn crosses_antimeridian(&self) -> bool {
+        self.min_lon > self.max_lon
+    }
+
+    /// Returns whether the point is in the box, including its edges
+    pub fn contains(&self, latitude: f64, longitude: f64) -> bool {
+        let in_lon = if self.crosses_antimeridian() {
+            longitude >= self.min_lon || longitude <= self.max_lon
+        } else {
+            longitude >= self.min_lon && longitude <= self.max_lon
+        };
+        in_lon && latitude >= self.min_lat && latitude <= self.max_lat
+    }
+
+    /// Returns whether the point is in the box. Points without coordinates are never in the box.
+    pub fn contains_point(&self, latitude: Option<f64>, longitude: Option<f64>) -> bool {
+        match (latitude, longitude) {
+            (Some(latitude), Some(longitude)) => self.contains(latitude, longitude),
+            _ => false,
+        }
+    }
+}
+
+/// The error when a string isn't a bounding box
+#[derive(Debug)]
+pub struct ParseBoundingBoxError(String);
+
+impl fmt::Display for ParseBoundingBoxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid bounding box {}, expected min_lon,min_lat,max_lon,max_lat",
+            self.0
+        )
+    }
+}
+
+impl Error for ParseBoundingBoxError {}
+
+impl FromStr for BoundingBox {
+    type Err = ParseBoundingBoxError;
+
+    /// Parses a bounding box written as `min_lon,min_lat,max_lon,max_lat`, the order used by GeoJSON
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let values = s
+            .split(',')
+            .map(|value| value.trim().parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| ParseBoundingBoxError(s.to_string()))?;
+        match values[..] {
+            [min_lon, min_lat, max_lon, max_lat] if min_lat <= max_lat => Ok(BoundingBox {
+                min_lon,
+                min_lat,
+                max_lon,
+                max_lat,
+            }),
+            _ => Err(ParseBoundingBoxError(s.to_string())),
+        }
+    }
+}
+
+/// Returns the `[longitude, latitude]` of a record serialized as JSON, the order used by GeoJSON and TopoJSON
+pub fn point_coordinates(record: &Value) -> Option<[f64; 2]> {
+    let latitude = record.get("latitude_deg")?.as_f64()?;
+    let longitude = record.get("longitude_deg")?.as_f64()?;
+    Some([longitude, latitude])
+}
+
+/// Converts records serialized as JSON into a GeoJSON FeatureCollection of points,
+/// located by their `latitude_deg` and `longitude_deg` fields, with the records as the properties.
+/// Records without coordinates are left out.
+pub fn feature_collection(records: &[Value]) -> Value {
+    let features: Vec<Value> = records
+        .iter()
+        .filter_map(|record| {
+            Some(json!({
+                "type": "Feature",
+                "geometry": {
+                    "type": "Point",
+                    "coordinates": point_coordinates(record)?,
+                },
+                "properties": record,
+            }))
+        })
+        .collect();
+    json!({
+        "type": "FeatureCollection",
+        "features": features,
+    })
+}
+
+/// Returns a `marker-symbol` for a navaid type, naming a [Maki](https://labs.mapbox.com/maki-icons/) icon
+/// as in the simplestyle spec, so that each family of navaids looks different on a map:
+/// triangles for VORs, squares for TACANs and DMEs, and circles for NDBs.
+/// Filled symbols have a paired DME or TACAN.
+pub fn navaid_marker_symbol(navaid_type: &str) -> &'static str {
+    match navaid_type {
+        "VOR" => "triangle-stroked",
+        "VOR-DME" | "VORTAC" => "triangle",
+        "TACAN" => "square",
+        "DME" => "square-stroked",
+        "NDB" => "circle-stroked",
+        "NDB-DME" => "circle",
+        _ => "marker",
+    }
+}
+
+/// Adds properties for styling a navaid serialized as JSON on a map: `navaid_type`, `frequency_mhz`,
+/// from `frequency_khz` (null if it isn't a number), and `marker-symbol`, from `navaid_marker_symbol`
+pub fn add_navaid_style(fields: &mut Map<String, Value>) {
+    let navaid_type = fields
+        .get("type")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let frequency_mhz = fields
+        .get("frequency_khz")
+        .and_then(Value::as_str)
+        .and_then(|frequency| frequency.trim().parse::<f64>().ok())
+        .map(|frequency_khz| frequency_khz / 1000.0);
+    fields.insert(
+        "marker-symbol".to_string(),
+        navaid_marker_symbol(&navaid_type).into(),
+    );
+    fields.insert("frequency_mhz".to_string(), json!(frequency_mhz));
+    fields.insert("navaid_type".to_string(), navaid_type.into());
+}
+
+/// The number of positions along each axis of a quantized TopoJSON topology. Over the whole world,
+/// a position is about 40 m across, much finer than the location of an airport or navaid
+pub const TOPOLOGY_QUANTIZATION: u32 = 1_000_000;
+
+/// Returns the `scale` and `translate` of a transform quantizing positions from `min` to `max`
+/// to integers from 0 to `TOPOLOGY_QUANTIZATION - 1`
+fn quantize_axis(min: f64, max: f64) -> (f64, f64) {
+    let scale = if max > min {
+        (max - min) / f64::from(TOPOLOGY_QUANTIZATION - 1)
+    } else {
+        1.0
+    };
+    (scale, min)
+}
+
+/// Converts records serialized as JSON into a TopoJSON topology with a single GeometryCollection
+/// of points named `object_name`, located and with properties as in `feature_collection`.
+/// The topology is quantized: its `transform` maps the integer coordinates of each point to its longitude
+/// and latitude, so that they are written in fewer digits. Points need no arcs, so `arcs` is empty.
+/// Records without coordinates are left out.
+pub fn topology(object_name: &str, records: &[Value]) -> Value {
+    let points: Vec<([f64; 2], &Value)> = records
+        .iter()
+        .filter_map(|record| Some((point_coordinates(record)?, record)))
+        .collect();
+    let bbox: Option<[f64; 4]> = points.iter().fold(None, |bbox, ([lon, lat], _)| {
+        Some(match bbox {
+            None => [*lon, *lat, *lon, *lat],
+            Some([min_lon, min_lat, max_lon, max_lat]) => [
+                f64::min(min_lon, *lon),
+                f64::min(min_lat, *lat),
+                f64::max(max_lon, *lon),
+                f64::max(max_lat, *lat),
+            ],
+        })
+    });
+
+    let mut topology = json!({
+        "type": "Topology",
+        "objects": {},
+        "arcs": [],
+    });
+    let mut geometries = Vec::new();
+    if let Some([min_lon, min_lat, max_lon, max_lat]) = bbox {
+        let (scale_x, translate_x) = quantize_axis(min_lon, max_lon);
+        let (scale_y, translate_y) = quantize_axis(min_lat, max_lat);
+        for ([lon, lat], record) in points {
+            let x = ((lon - translate_x) / scale_x).round() as u32;
+            let y = ((lat - translate_y) / scale_y).round() as u32;
+            geometries.push(json!({
+                "type": "Point",
+                "coordinates": [x, y],
+                "properties": record,
+            }));
+        }
+        topology["bbox"] = json!([min_lon, min_lat, max_lon, max_lat]);
+        topology["transform"] = json!({
+            "scale": [scale_x, scale_y],
+            "translate": [translate_x, translate_y],
+        });
+    }
+    topology["objects"][object_name] = json!({
+        "type": "GeometryCollection",
+        "geometries": geometries,
+    });
+    topology
+}
+
+/// Replaces the `latitude_deg`, `longitude_deg` and `elevation_ft` fields of a record serialized as JSON
+/// with a `coordinates` object of `lat`, `lon` and `elevation_ft`, in place of `latitude_deg`.
+/// `coordinates` is null if the record has no latitude or longitude.
+pub fn nest_coordinates(fields: Map<String, Value>) -> Map<String, Value> {
+    let latitude = fields.get("latitude_deg").cloned().unwrap_or(Value::Null);
+    let longitude = fields.get("longitude_deg").cloned().unwrap_or(Value::Null);
+    let elevation = fields.get("elevation_ft").cloned().unwrap_or(Value::Null);
+    let coordinates = if latitude.is_null() || longitude.is_null() {
+        Value::Null
+    } else {
+        json!({
+            "lat": latitude,
+            "lon": longitude,
+            "elevation_ft": elevation,
+        })
+    };
+
+    let mut coordinates = Some(coordinates);
+    fields
+        .into_iter()
+        .filter_map(|(key, value)| match key.as_str() {
+            "latitude_deg" => coordinates
+                .take()
+                .map(|coordinates| ("coordinates".to_string(), coordinates)),
+            "longitude_deg" | "elevation_ft" => None,
+            _ => Some((key, value)),
+        })
+        .collect()
+}
+
+/// The mean radius of the Earth, in nautical miles
+const EARTH_RADIUS_NM: f64 = 3440.065;
+
+/// Returns the great-circle distance between two points in nautical miles, with the haversine formula
+pub fn distance_nm(latitude1: f64, longitude1: f64, latitude2: f64, longitude2: f64) -> f64 {
+    let (latitude1, latitude2) = (latitude1.to_radians(), latitude2.to_radians());
+    let half_latitude = (latitude2 - latitude1) / 2.0;
+    let half_longitude = (longitude2 - longitude1).to_radians() / 2.0;
+    let haversine = half_latitude.sin().powi(2)
+        + latitude1.cos() * latitude2.cos() * half_longitude.sin().powi(2);
+    2.0 * EARTH_RADIUS_NM * haversine.sqrt().asin()
+}
+
+/// The deepest zoom level of slippy map tiles, as served by most tile servers
+pub const MAX_ZOOM: u8 = 22;
+
+/// Returns the `x` and `y` of the slippy map tile containing the point at the zoom level,
+/// as used by OpenStreetMap. Latitudes beyond the Web Mercator limit of ±85.0511° are in the edge tiles.
+/// Zoom levels deeper than `MAX_ZOOM` are treated as `MAX_ZOOM`, so that the tile coordinates fit.
+pub fn tile_for(latitude: f64, longitude: f64, zoom: u8) -> (u32, u32) {
+    let zoom = zoom.min(MAX_ZOOM);
+    let tile_count = 2f64.powi(zoom.into());
+    let max_index = tile_count - 1.0;
+    let x = ((longitude + 180.0) / 360.0 * tile_count).floor();
+    let latitude = latitude.to_radians();
+    let y = ((1.0 - latitude.tan().asinh() / PI) / 2.0 * tile_count).floor();
+    (
+        x.clamp(0.0, max_index) as u32,
+        y.clamp(0.0, max_index) as u32,
+    )
+}
+
+/// Groups airports by the slippy map tile they are in at the zoom level, keyed by `(x, y)`.
+/// Airports without coordinates are left out.
+pub fn tile_airports(airports: &[Airport], zoom: u8) -> BTreeMap<(u32, u32), Vec<&Airport>> {
+    let mut tiles: BTreeMap<(u32, u32), Vec<&Airport>> = BTreeMap::new();
+    for airport in airports {
+        if let (Some(latitude), Some(longitude)) = (airport.latitude_deg, airport.longitude_deg) {
+            tiles
+                .entry(tile_for(latitude, longitude, zoom))
+                .or_default()
+                .push(airport);
+        }
+    }
+    tiles
+}
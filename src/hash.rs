@@ -0,0 +1,12 @@
+use crate::error::Result;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+/// Computes a hash of a record for change detection.
+///
+/// This is the hex-encoded SHA-256 digest of the record's compact JSON form,
+/// so it is the same across runs and platforms as long as the record is unchanged.
+pub fn record_hash<T: Serialize>(record: &T) -> Result<String> {
+    let json = serde_json::to_vec(record)?;
+    Ok(format!("{:x}", Sha256::digest(&json)))
+}
@@ -0,0 +1,114 @@
+//! ourairports
+//! Reads data from OurAirports.
+//!
+//! Dataset format information is from https://ourairports.com/help/data-dictionary.html
+//! with some modifications.
+
+/// Lookup tables between airport code systems
+mod codes;
+/// Fields computed from records with expressions
+mod compute;
+/// The continents used by OurAirports
+mod continent;
+/// Coarse bounding boxes of the countries
+mod country_bounds;
+/// Countries and regions summarised with counts from the other datasets
+mod country_info;
+/// All of the datasets together, indexed for lookups
+mod data;
+/// Merging duplicate records
+mod dedupe;
+/// Everything known about one airport, joined from all of the datasets
+mod detail;
+/// Counting the distinct values of fields
+mod distinct;
+/// BSON documents of records, for MongoDB
+#[cfg(feature = "bson")]
+mod document;
+/// Filling in and adding fields from related datasets
+mod enrich;
+/// The error type of the library
+mod error;
+/// FlatGeobuf files of points, for GIS software
+#[cfg(feature = "flatgeobuf")]
+mod fgb;
+/// Safe automatic corrections of the data
+mod fix;
+/// Formatting JSON output
+mod format;
+/// Geographic filtering and GeoJSON output
+mod geo;
+/// Hashing records for change detection
+mod hash;
+/// Contains all of the structs of data types available from OurAirports
+/// as well as the methods used to instantiate one.
+mod ourairports;
+/// Parsing csv data into records
+mod parse;
+/// Filtering records as they are parsed
+mod pipeline;
+/// Queries of records loaded once, for the repl
+mod query;
+/// The types of airports and navaids
+mod record_type;
+/// Searching airports by name
+mod search;
+/// Sorting records by a field
+mod sort;
+/// Reading the data from a local file or the internet
+mod source;
+/// Which aircraft the runways of airports are suitable for
+mod suitability;
+/// Writing records as delimited tables
+mod table;
+/// Checking the data for issues
+mod validate;
+/// Conversions between records and `serde_json::Value`
+mod value;
+
+pub use codes::{iata_to_icao, icao_to_iata};
+pub use compute::{ComputedField, ParseComputedFieldError};
+pub use continent::{Continent, ParseContinentError};
+pub use country_info::{airports_per_region, CountryInfo};
+pub use data::OurAirportsData;
+pub use dedupe::dedupe_frequencies;
+pub use detail::{AirportDetail, NearbyNavaid};
+pub use distinct::{count_distinct, ValueCount};
+#[cfg(feature = "bson")]
+pub use document::to_document;
+pub use enrich::{
+    country_continents, country_names, navaid_band, region_names, regions_by_country,
+    runways_with_airports, CountryRegions, NavaidBand,
+};
+pub use error::Error;
+#[cfg(feature = "flatgeobuf")]
+pub use fgb::to_flatgeobuf;
+pub use fix::{fix_csv, Fix};
+pub use format::{
+    sort_keys, to_string_record_lines, to_string_with_formatter, CompactFloatsFormatter,
+    FixedDecimalsFormatter, RecordLinesFormatter,
+};
+pub use geo::{
+    add_navaid_style, distance_nm, feature_collection, navaid_marker_symbol, nest_coordinates,
+    point_coordinates, tile_airports, tile_for, topology, BoundingBox, ParseBoundingBoxError,
+    MAX_ZOOM, TOPOLOGY_QUANTIZATION,
+};
+pub use hash::record_hash;
+pub use ourairports::*;
+pub use parse::{check_columns, non_blank_rows, parse_file, parse_from_bytes, parse_from_reader};
+pub use pipeline::{HasCoordinates, HasCountry, HasType, Pipeline};
+pub use query::{NearbyRecord, ParseQueryError, Query, QueryResult};
+pub use record_type::{AirportType, NavaidType, ParseAirportTypeError, ParseNavaidTypeError};
+pub use search::{fuzzy_search_airports, keyword_index, search_airports, ScoredAirport};
+pub use sort::{cmp_field_values, sort_by_field};
+pub use source::{
+    dataset_file, download_records, download_text, download_text_limited, fetch_info, read_archive,
+    read_file, read_text, RecordStream, RemoteInfo, RequestType, Source, ATTRIBUTION, LICENSE,
+};
+pub use suitability::{max_runway_lengths, AircraftCategory, RunwayThresholds};
+pub use table::{to_delimited, to_sql, to_tuples};
+pub use validate::{
+    mismatched_runway_headings, misplaced_airports, out_of_band_frequencies, swapped_coordinates,
+    unknown_country_codes, unknown_region_codes, CodeCount, CoordinateIssue, FrequencyIssue,
+    LocationIssue, ValidationReport,
+};
@@ -3,42 +3,45 @@
 
 /**
  * TODO
- * tests
  * example code
- * turn this to a library
- * condense the code somehow
  */
-use anyhow::{Context, Result};
-use clap::Clap;
+use anyhow::{anyhow, Context, Result};
+use clap::{ArgEnum, Clap};
+use env_logger::Env;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use futures::StreamExt;
 use human_panic::setup_panic;
+use log::{debug, info, warn};
+use ourairports::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::ser::{CompactFormatter, Formatter, PrettyFormatter};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::env;
 use std::fs;
+use std::future::Future;
+use std::io::{self, IsTerminal, Write};
+use std::iter;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::thread::{self, ScopedJoinHandle};
 
-/// Airport data URL
-const AIRPORT_URL: &str = "https://ourairports.com/data/airports.csv";
-
-/// Airport frequency data URL
-const AIRPORT_FREQUENCY_URL: &str = "https://ourairports.com/data/airport-frequencies.csv";
-
-/// Runway data URL
-const RUNWAY_URL: &str = "https://ourairports.com/data/runways.csv";
-
-/// navaid data URL
-const NAVAID_URL: &str = "https://ourairports.com/data/navaids.csv";
-
-/// country data URL
-const COUNTRY_URL: &str = "https://ourairports.com/data/countries.csv";
-
-/// region data URL
-const REGION_URL: &str = "https://ourairports.com/data/regions.csv";
-
-// import ourairports module and all structs
-/// Contains all of the structs of data types available from OurAirports
-/// as well as the methods used to instantiate one.
-///
-/// Dataset format information is from https://ourairports.com/help/data-dictionary.html
-/// with some modifications.
-mod ourairports;
-use ourairports::*;
+/// The command line arguments
+#[derive(Clap)]
+struct Opts {
+    /// Only log warnings and errors, without progress
+    #[clap(short = 'q', long = "quiet", global = true)]
+    quiet: bool,
+    /// Also log details, such as the size of the output written
+    #[clap(short = 'v', long = "verbose", global = true, conflicts_with = "quiet")]
+    verbose: bool,
+    #[clap(subcommand)]
+    command: Cli,
+}
 
 /// Converts data from OurAirports to JSON format.
 /// You need to download the data on your own from https://ourairports.com/data/
@@ -46,80 +49,306 @@ use ourairports::*;
 enum Cli {
     /// Convert airport data
     Airport {
-        #[clap(parse(from_os_str))]
-        /// Airport data file from openflights
-        input_file: Option<std::path::PathBuf>,
-        #[clap(short = 'o', long = "output")]
-        /// Output file
-        output_file: Option<std::path::PathBuf>,
-        /// Pretty print output
-        #[clap(short = 'p', long = "pretty-print")]
-        pretty_print: bool,
+        #[clap(flatten)]
+        opts: ConvertOpts,
+        #[clap(flatten)]
+        keyword_opts: KeywordOpts,
+        #[clap(flatten)]
+        continent_opts: ContinentOpts,
+        #[clap(flatten)]
+        country_opts: CountryOpts,
+        #[clap(flatten)]
+        bbox_opts: BboxOpts,
+        /// Uppercase the `ident`, `gps_code`, `iata_code` and `local_code` fields
+        #[clap(long = "normalize-codes")]
+        normalize_codes: bool,
+        /// Fill in blank continents with the continent of the airport's country
+        #[clap(long = "fill-continent")]
+        fill_continent: bool,
+        /// Add an `icao_code` field: `gps_code` if it looks like an ICAO code (four letters), otherwise `ident`
+        #[clap(long = "with-icao-code")]
+        with_icao_code: bool,
+        /// Only keep airports with an IATA code
+        #[clap(long = "only-with-iata")]
+        only_with_iata: bool,
+        /// Sort the keywords of each record alphabetically, ignoring case
+        #[clap(long = "sort-keywords")]
+        sort_keywords: bool,
+        /// Write a blank `scheduled_service` as null, for unknown, instead of failing to parse it
+        #[clap(long = "tristate-scheduled")]
+        tristate_scheduled: bool,
+        /// Add `max_runway_length_ft`, the length of the longest open runway, and `suitable_for`,
+        /// the largest category of aircraft it suits (heavy_jet, regional_jet or ga), from the runway data
+        #[clap(long = "runway-suitability")]
+        runway_suitability: bool,
+        /// Shortest runway in feet suitable for heavy jets, with --runway-suitability
+        #[clap(long = "heavy-jet-length", default_value = "8000")]
+        heavy_jet_length: u32,
+        /// Shortest runway in feet suitable for regional jets, with --runway-suitability
+        #[clap(long = "regional-jet-length", default_value = "5000")]
+        regional_jet_length: u32,
+        /// Add `country_name` and `region_name` fields from the country and region data
+        #[clap(long = "enrich")]
+        enrich: bool,
+        #[clap(long = "countries", parse(from_os_str))]
+        /// Country data file, for options that need it. Downloaded if not given
+        countries_file: Option<PathBuf>,
+        #[clap(long = "regions", parse(from_os_str))]
+        /// Region data file, for options that need it. Downloaded if not given
+        regions_file: Option<PathBuf>,
+        #[clap(long = "runways", parse(from_os_str))]
+        /// Runway data file, for options that need it. Downloaded if not given
+        runways_file: Option<PathBuf>,
     },
     /// Convert airport frequency data
     AirportFrequency {
-        #[clap(parse(from_os_str))]
-        /// Airport data file from openflights
-        input_file: Option<std::path::PathBuf>,
-        #[clap(short = 'o', long = "output")]
-        /// Output file
-        output_file: Option<std::path::PathBuf>,
-        /// Pretty print output
-        #[clap(short = 'p', long = "pretty-print")]
-        pretty_print: bool,
+        #[clap(flatten)]
+        opts: ConvertOpts,
+        /// Merge the frequencies of an airport with the same type and frequency, keeping the longest description
+        #[clap(long = "dedupe-frequencies")]
+        dedupe_frequencies: bool,
     },
     /// Convert runway data
     Runway {
-        #[clap(parse(from_os_str))]
-        /// Airport data file from openflights
-        input_file: Option<std::path::PathBuf>,
-        #[clap(short = 'o', long = "output")]
-        /// Output file
-        output_file: Option<std::path::PathBuf>,
-        /// Pretty print output
-        #[clap(short = 'p', long = "pretty-print")]
-        pretty_print: bool,
+        #[clap(flatten)]
+        opts: ConvertOpts,
+        /// Map the heading fields into the range [0, 360)
+        #[clap(long = "normalize-headings")]
+        normalize_headings: bool,
+        /// Add a `heading_consistency` field: whether the two ends of the runway are roughly opposite each other,
+        /// or null if either heading is missing
+        #[clap(long = "with-heading-consistency")]
+        with_heading_consistency: bool,
+        /// How many degrees the two ends of a runway may be from exactly opposite for --with-heading-consistency
+        #[clap(long = "heading-tolerance", default_value = "5")]
+        heading_tolerance: f64,
     },
     /// Convert navaid data
     Navaid {
-        #[clap(parse(from_os_str))]
-        /// Airport data file from openflights
-        input_file: Option<std::path::PathBuf>,
-        #[clap(short = 'o', long = "output")]
-        /// Output file
-        output_file: Option<std::path::PathBuf>,
-        /// Pretty print output
-        #[clap(short = 'p', long = "pretty-print")]
-        pretty_print: bool,
+        #[clap(flatten)]
+        opts: ConvertOpts,
+        #[clap(flatten)]
+        continent_opts: ContinentOpts,
+        #[clap(flatten)]
+        country_opts: CountryOpts,
+        #[clap(flatten)]
+        bbox_opts: BboxOpts,
+        #[clap(long = "countries", parse(from_os_str))]
+        /// Country data file, to find the continent of each navaid with --continent. Downloaded if not given
+        countries_file: Option<PathBuf>,
+        /// Add a `band` field: the radio band of the navaid, LF/MF, VHF or UHF, from its type and frequency
+        #[clap(long = "with-band")]
+        with_band: bool,
     },
     /// Convert country data
     Country {
-        #[clap(parse(from_os_str))]
-        /// Airport data file from openflights
-        input_file: Option<std::path::PathBuf>,
-        #[clap(short = 'o', long = "output")]
-        /// Output file
-        output_file: Option<std::path::PathBuf>,
-        /// Pretty print output
-        #[clap(short = 'p', long = "pretty-print")]
-        pretty_print: bool,
+        #[clap(flatten)]
+        opts: ConvertOpts,
+        #[clap(flatten)]
+        keyword_opts: KeywordOpts,
+        #[clap(flatten)]
+        continent_opts: ContinentOpts,
+        #[clap(flatten)]
+        country_opts: CountryOpts,
+        /// Sort the keywords of each record alphabetically, ignoring case
+        #[clap(long = "sort-keywords")]
+        sort_keywords: bool,
+        /// Add a `regions` array to each country: the codes of its regions, joined from the region data on `iso_country`
+        #[clap(long = "with-regions")]
+        with_regions: bool,
+        /// With --with-regions, add the whole regions instead of their codes
+        #[clap(long = "full", requires = "with-regions")]
+        full: bool,
+        #[clap(long = "regions", parse(from_os_str))]
+        /// Region data file, for --with-regions. Downloaded if not given
+        regions_file: Option<PathBuf>,
     },
     /// Convert region data
     Region {
-        #[clap(parse(from_os_str))]
-        /// Airport data file from openflights
-        input_file: Option<std::path::PathBuf>,
+        #[clap(flatten)]
+        opts: ConvertOpts,
+        #[clap(flatten)]
+        keyword_opts: KeywordOpts,
+        #[clap(flatten)]
+        continent_opts: ContinentOpts,
+        #[clap(flatten)]
+        country_opts: CountryOpts,
+        /// Sort the keywords of each record alphabetically, ignoring case
+        #[clap(long = "sort-keywords")]
+        sort_keywords: bool,
+    },
+    /// Search airports by name, municipality or keywords
+    Search {
+        /// Text to search for
+        query: String,
+        #[clap(flatten)]
+        input: InputOpts,
+        #[clap(flatten)]
+        output: OutputOpts,
+        #[clap(flatten)]
+        keyword_opts: KeywordOpts,
+        /// Rank airports by a fuzzy match score instead of matching substrings
+        #[clap(long = "fuzzy")]
+        fuzzy: bool,
+        /// Number of results to return with --fuzzy
+        #[clap(long = "limit", default_value = "10")]
+        limit: usize,
+    },
+    /// Show a country together with counts of its airports and regions
+    CountryInfo {
+        /// The ISO 3166:1-alpha2 code of the country
+        #[clap(long = "code")]
+        code: String,
+        #[clap(flatten)]
+        input: InputOpts,
+        #[clap(long = "airports", parse(from_os_str))]
+        /// Airport data file, to count the airports in the country
+        airports_file: Option<PathBuf>,
+        #[clap(long = "regions", parse(from_os_str))]
+        /// Region data file, to count the regions in the country
+        regions_file: Option<PathBuf>,
+        #[clap(flatten)]
+        output: OutputOpts,
+        #[clap(flatten)]
+        keyword_opts: KeywordOpts,
+    },
+    /// Count the airports in each region, as an object from each region code to its count
+    RegionCounts {
+        #[clap(flatten)]
+        input: InputOpts,
+        #[clap(long = "regions", parse(from_os_str))]
+        /// Region data file, to include the regions without any airports with a count of 0
+        regions_file: Option<PathBuf>,
+        #[clap(flatten)]
+        output: OutputOpts,
+        #[clap(flatten)]
+        keyword_opts: KeywordOpts,
+    },
+    /// Check the datasets for issues, writing a JSON report of them
+    Validate {
+        #[clap(flatten)]
+        opts: ValidateOpts,
+        #[clap(flatten)]
+        keyword_opts: KeywordOpts,
+    },
+    /// Show how fresh each dataset on the OurAirports website is, without downloading it
+    Info {
+        #[clap(flatten)]
+        output: OutputOpts,
+    },
+    /// Convert all six datasets into a single JSON document
+    Bundle {
+        #[clap(flatten)]
+        opts: BundleOpts,
+        #[clap(flatten)]
+        keyword_opts: KeywordOpts,
+    },
+    /// Write a lookup table from the IATA code of each airport to its ICAO code
+    Codes {
+        #[clap(flatten)]
+        input: InputOpts,
+        #[clap(flatten)]
+        output: OutputOpts,
+        /// Map ICAO codes to IATA codes instead
+        #[clap(long = "reverse")]
+        reverse: bool,
+    },
+    /// Show everything about one airport: its runways, frequencies, nearby navaids and country and region names
+    AirportDetail {
+        /// The ICAO code of the airport, e.g. EGLL
+        #[clap(long = "icao")]
+        icao: String,
+        #[clap(long = "input-dir", parse(from_os_str))]
+        /// Directory or ZIP archive containing all of the data files from OurAirports, named as on the website. Downloaded if not given
+        input_dir: Option<PathBuf>,
+        /// Include navaids within this many nautical miles of the airport
+        #[clap(long = "radius-nm", default_value = "25")]
+        radius_nm: f64,
+        #[clap(flatten)]
+        output: OutputOpts,
+    },
+    /// Write one CSV table of every runway, joined with the name, country and coordinates of its airport
+    MergeDatasets {
+        #[clap(long = "runways", parse(from_os_str))]
+        /// Runway data file. Downloaded if not given
+        runways_file: Option<PathBuf>,
+        #[clap(long = "airports", parse(from_os_str))]
+        /// Airport data file. Downloaded if not given
+        airports_file: Option<PathBuf>,
         #[clap(short = 'o', long = "output")]
         /// Output file
-        output_file: Option<std::path::PathBuf>,
-        /// Pretty print output
-        #[clap(short = 'p', long = "pretty-print")]
-        pretty_print: bool,
+        output_file: Option<PathBuf>,
+        #[clap(flatten)]
+        keyword_opts: KeywordOpts,
+    },
+    /// Show the frequencies of one airport
+    Frequencies {
+        /// The `ident` of the airport, e.g. EGLL
+        #[clap(long = "airport")]
+        airport: String,
+        #[clap(flatten)]
+        input: InputOpts,
+        #[clap(flatten)]
+        output: OutputOpts,
+    },
+    /// Write the airports in each slippy map tile at a zoom level to its own file, z/x/y.json
+    Tiles {
+        #[clap(flatten)]
+        input: InputOpts,
+        /// The zoom level of the tiles, from 0 for a single tile of the whole world to 22
+        #[clap(long = "zoom", parse(try_from_str = parse_zoom))]
+        zoom: u8,
+        #[clap(long = "output-dir", parse(from_os_str))]
+        /// Directory to write the tiles to
+        output_dir: PathBuf,
+        #[clap(flatten)]
+        output: OutputOpts,
+    },
+    /// Build an index from each keyword to the ids of the records that have it
+    IndexKeywords {
+        /// The dataset to index
+        #[clap(arg_enum)]
+        dataset: KeywordDataset,
+        #[clap(flatten)]
+        input: InputOpts,
+        #[clap(flatten)]
+        output: OutputOpts,
+        #[clap(flatten)]
+        keyword_opts: KeywordOpts,
+    },
+    /// Count the distinct values of a field, most common first
+    Distinct {
+        /// The dataset to count
+        #[clap(arg_enum)]
+        dataset: Dataset,
+        /// The field to count, as named in the JSON output, e.g. type
+        #[clap(long = "field")]
+        field: String,
+        #[clap(flatten)]
+        input: InputOpts,
+        #[clap(flatten)]
+        output: OutputOpts,
+        #[clap(flatten)]
+        keyword_opts: KeywordOpts,
+    },
+    /// Load airports or navaids once, then answer queries read from stdin one per line until EOF:
+    /// `country CODE`, `type TYPE`, `near LAT LON RADIUS_NM` or `count`
+    Repl {
+        /// The dataset to query
+        #[clap(arg_enum)]
+        dataset: QueryDataset,
+        #[clap(flatten)]
+        input: InputOpts,
+        #[clap(flatten)]
+        output: OutputOpts,
+        #[clap(flatten)]
+        keyword_opts: KeywordOpts,
     },
 }
 
-/// Request data type
-enum RequestType {
+/// All of the datasets
+#[derive(ArgEnum)]
+enum Dataset {
     Airport,
     AirportFrequency,
     Runway,
@@ -128,268 +357,2566 @@ enum RequestType {
     Region,
 }
 
-/// Reads the csv data from a local file or the internet
-#[tokio::main]
-async fn read_text(
-    file_path: &Option<std::path::PathBuf>,
-    request_type: RequestType,
-) -> Result<String> {
-    if let Some(path) = file_path {
-        eprintln!("Reading file {}", path.to_string_lossy());
-        let content = fs::read_to_string(&path)
-            .context(format!("Could not open file: {}", path.to_string_lossy()))?;
-        Ok(content)
+impl Dataset {
+    /// Returns the type of the records of the dataset
+    fn request_type(&self) -> RequestType {
+        match self {
+            Dataset::Airport => RequestType::Airport,
+            Dataset::AirportFrequency => RequestType::AirportFrequency,
+            Dataset::Runway => RequestType::Runway,
+            Dataset::Navaid => RequestType::Navaid,
+            Dataset::Country => RequestType::Country,
+            Dataset::Region => RequestType::Region,
+        }
+    }
+}
+
+/// Datasets that can be queried with the repl
+#[derive(ArgEnum)]
+enum QueryDataset {
+    Airport,
+    Navaid,
+}
+
+/// Datasets that have keywords
+#[derive(ArgEnum)]
+enum KeywordDataset {
+    Airport,
+    Country,
+    Region,
+}
+
+/// Formats that records can be written in
+#[derive(ArgEnum, Clone, Copy, PartialEq)]
+enum Format {
+    Json,
+    Csv,
+    Tsv,
+    Topojson,
+    Tuples,
+    JsonlGz,
+    Bson,
+    Sql,
+    Flatgeobuf,
+}
+
+impl Format {
+    /// Returns the extension of files in the format
+    fn extension(&self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Csv => "csv",
+            Format::Tsv => "tsv",
+            Format::Topojson => "topojson",
+            Format::Tuples => "json",
+            Format::JsonlGz => "jsonl.gz",
+            Format::Bson => "bson",
+            Format::Sql => "sql",
+            Format::Flatgeobuf => "fgb",
+        }
+    }
+}
+
+/// Options for filtering records by continent
+#[derive(Clap)]
+struct ContinentOpts {
+    /// Only keep records on this continent: AF, AN, AS, EU, NA, OC or SA. Can be repeated
+    #[clap(long = "continent", number_of_values = 1)]
+    continent: Vec<Continent>,
+}
+
+impl ContinentOpts {
+    /// Returns whether a record with the given continent code is kept
+    fn matches(&self, code: &str) -> bool {
+        self.continent.is_empty()
+            || self
+                .continent
+                .iter()
+                .any(|continent| continent.code() == code)
+    }
+}
+
+/// Parses an ISO 3166:1-alpha2 country code, such as "GB": two letters, in any case
+fn parse_country_code(code: &str) -> Result<String, String> {
+    if code.len() == 2 && code.chars().all(|c| c.is_ascii_alphabetic()) {
+        Ok(code.to_ascii_uppercase())
     } else {
-        let url = match request_type {
-            RequestType::Airport => AIRPORT_URL,
-            RequestType::AirportFrequency => AIRPORT_FREQUENCY_URL,
-            RequestType::Runway => RUNWAY_URL,
-            RequestType::Navaid => NAVAID_URL,
-            RequestType::Country => COUNTRY_URL,
-            RequestType::Region => REGION_URL,
-        };
-        eprintln!("Downloading from {}", url);
-        let resp = reqwest::get(url)
-            .await
-            .context(format!("Could not open page: {}", url))?
-            .text()
-            .await?;
-        Ok(resp)
+        Err(format!(
+            "invalid country code {}, expected two letters such as GB",
+            code
+        ))
+    }
+}
+
+/// Parses a zoom level of slippy map tiles, from 0 to `MAX_ZOOM`
+fn parse_zoom(zoom: &str) -> Result<u8, String> {
+    match zoom.parse() {
+        Ok(zoom) if zoom <= MAX_ZOOM => Ok(zoom),
+        _ => Err(format!(
+            "invalid zoom level {}, expected 0 to {}",
+            zoom, MAX_ZOOM
+        )),
+    }
+}
+
+/// Options for filtering records by country
+#[derive(Clap)]
+struct CountryOpts {
+    /// Only keep records in the country with this code, e.g. GB. Can be repeated
+    #[clap(long = "country", number_of_values = 1, parse(try_from_str = parse_country_code))]
+    country: Vec<String>,
+    /// Leave out records in the country with this code, after --country. Can be repeated
+    #[clap(long = "exclude-country", number_of_values = 1, parse(try_from_str = parse_country_code))]
+    exclude_country: Vec<String>,
+}
+
+impl CountryOpts {
+    /// Returns whether a record with the given country code is kept
+    fn matches(&self, code: &str) -> bool {
+        (self.country.is_empty() || self.country.iter().any(|country| country == code))
+            && !self.exclude_country.iter().any(|country| country == code)
     }
 }
 
-/// Converts airport data to JSON
-fn convert_airport_data(
-    file_path: &Option<std::path::PathBuf>,
+/// Options for filtering records by location
+#[derive(Clap)]
+struct BboxOpts {
+    /// Only keep records in this box, written as min_lon,min_lat,max_lon,max_lat.
+    /// If min_lon is greater than max_lon, the box crosses the 180° line.
+    /// Records without coordinates are left out
+    #[clap(long = "bbox", allow_hyphen_values = true)]
+    bbox: Option<BoundingBox>,
+}
+
+impl BboxOpts {
+    /// Returns whether a record at the given coordinates is kept
+    fn matches(&self, latitude: Option<f64>, longitude: Option<f64>) -> bool {
+        self.bbox
+            .is_none_or(|bbox| bbox.contains_point(latitude, longitude))
+    }
+}
+
+/// Options for where to read a dataset from
+#[derive(Clap, Clone)]
+struct InputOpts {
+    #[clap(parse(from_os_str))]
+    /// Data file from OurAirports, or a ZIP archive containing it named as on the website. Downloaded if not given
+    input_file: Option<PathBuf>,
+    /// Download the data from this URL instead of the input file or the OurAirports website
+    #[clap(long = "from-url")]
+    from_url: Option<String>,
+    /// Only download the first N bytes of the data, dropping the last, partial row. For testing
+    #[clap(long = "max-bytes", hidden = true)]
+    max_bytes: Option<usize>,
+}
+
+/// Options for where and how to write the output
+#[derive(Clap, Clone)]
+struct OutputOpts {
+    /// Output file. Converters can be given several, each written in the format of its extension
+    #[clap(short = 'o', long = "output", number_of_values = 1)]
+    output_files: Vec<PathBuf>,
+    /// Pretty print output
+    #[clap(short = 'p', long = "pretty-print")]
     pretty_print: bool,
-) -> Result<String> {
-    // read original file as csv
-    let data = read_text(&file_path, RequestType::Airport)?;
-    eprintln!("Converting data");
-    let mut rdr = csv::Reader::from_reader(data.as_bytes());
+    /// Pretty print output with one line per record
+    #[clap(long = "pretty-compact", conflicts_with = "pretty-print")]
+    pretty_compact: bool,
+    /// Pretty print output with the keys of every object sorted alphabetically
+    #[clap(long = "pretty-sorted-keys", conflicts_with_all = &["pretty-print", "pretty-compact"])]
+    pretty_sorted_keys: bool,
+    /// Write floats in JSON, such as coordinates, as numbers with this many decimals
+    #[clap(long = "decimals")]
+    decimals: Option<usize>,
+    /// Write every float in JSON with as few digits as it needs once rounded to 15 significant digits,
+    /// e.g. 51.47 rather than 51.470000000000006. This is lossy: it applies to all floats, coordinates,
+    /// elevations, headings, magnetic variations and --compute results alike, and drops any 16th or 17th digit
+    #[clap(long = "compact-floats", conflicts_with = "decimals")]
+    compact_floats: bool,
+}
 
-    // airport list
-    let mut airport_list: Vec<Airport> = Vec::new();
+impl OutputOpts {
+    /// Returns the output file, if given, for commands that write a single output
+    fn output_file(&self) -> Result<Option<&Path>> {
+        match self.output_files.as_slice() {
+            [] => Ok(None),
+            [output_file] => Ok(Some(output_file)),
+            _ => Err(anyhow!(
+                "only the converters can write more than one --output"
+            )),
+        }
+    }
+}
+
+/// Options shared by all of the converters
+#[derive(Clap, Clone)]
+struct ConvertOpts {
+    #[clap(flatten)]
+    input: InputOpts,
+    #[clap(flatten)]
+    output: OutputOpts,
+    /// Wrap the output in an object containing where the data was read from, how fresh it is and its attribution.
+    /// With --geojson, these are the properties of the FeatureCollection instead
+    #[clap(long = "with-metadata")]
+    with_metadata: bool,
+    /// Wrap the output in an object of `count`, the number of records, and `records`, the array of them
+    #[clap(long = "with-count", conflicts_with_all = &["geojson", "group-by"])]
+    with_count: bool,
+    /// Only check that the conversion succeeds, with every record parsing, reporting how many records it keeps
+    /// without writing any output
+    #[clap(long = "dry-run")]
+    dry_run: bool,
+    /// Add a `_hash` field to each record with a hash of its contents, for change detection
+    #[clap(long = "with-hash")]
+    with_hash: bool,
+    /// Add a `_source_line` field to each record with the line of the csv data it starts on
+    #[clap(long = "with-line")]
+    with_line: bool,
+    /// Write a GeoJSON FeatureCollection of points instead, for airports and navaids.
+    /// Records without coordinates are left out
+    #[clap(long = "geojson")]
+    geojson: bool,
+    /// Replace the latitude, longitude and elevation fields of airports and navaids with a `coordinates` object
+    /// of `lat`, `lon` and `elevation_ft`, which is null if the record has no coordinates
+    #[clap(long = "nest-coords", conflicts_with = "geojson")]
+    nest_coords: bool,
+    /// Add a field computed from the other fields of each record, written as NAME=EXPR,
+    /// e.g. elev_m="elevation_ft * 0.3048". Null where the expression can't be evaluated
+    #[clap(long = "compute", number_of_values = 1)]
+    compute: Vec<ComputedField>,
+    /// Only output these fields of each record, separated by commas, e.g. ident,name
+    #[clap(
+        long = "fields",
+        use_delimiter = true,
+        require_delimiter = true,
+        conflicts_with_all = &["exclude-fields", "geojson"]
+    )]
+    fields: Vec<String>,
+    /// Only output the fields listed in this file, separated by newlines or commas, as for --fields
+    #[clap(
+        long = "fields-file",
+        parse(from_os_str),
+        conflicts_with_all = &["fields", "exclude-fields", "geojson"]
+    )]
+    fields_file: Option<PathBuf>,
+    /// Output every field of each record except these, separated by commas, e.g. keywords,home_link
+    #[clap(
+        long = "exclude-fields",
+        use_delimiter = true,
+        require_delimiter = true,
+        conflicts_with = "geojson"
+    )]
+    exclude_fields: Vec<String>,
+    /// Output format
+    #[clap(long = "format", arg_enum, default_value = "json")]
+    format: Format,
+    /// Name of the table created by --format sql. Defaults to the name of the dataset, e.g. airports
+    #[clap(long = "table")]
+    table: Option<String>,
+    /// With --format jsonl-gz, add the records to the end of the output file instead of replacing it,
+    /// to build one file from several runs
+    #[clap(long = "append", requires = "output-files")]
+    append: bool,
+    /// Fail if the output would be larger than N bytes, counted as written to the file or stdout:
+    /// compressed for jsonl-gz, and with the newline that ends output to stdout. Streamed formats fail
+    /// as soon as they reach it, leaving the output written so far, unless it was appended with --append
+    #[clap(long = "max-output-bytes")]
+    max_output_bytes: Option<usize>,
+    /// Join the keywords of each record into one cell with --format csv, tsv or sql,
+    /// instead of writing them as a JSON array
+    #[clap(long = "flatten-keywords")]
+    flatten_keywords: bool,
+    /// Delimiter between the keywords joined by --flatten-keywords
+    #[clap(long = "flatten-delimiter", default_value = ",")]
+    flatten_delimiter: String,
+    /// Reuse the records selected by a previous conversion with the same options and input data,
+    /// cached in this directory, whatever file they are written to. Not used with --split-by,
+    /// or with options that read other datasets
+    #[clap(long = "cache-dir", parse(from_os_str))]
+    cache_dir: Option<PathBuf>,
+    /// Trim the whitespace around every field, such as "EGLL " in a code. Keywords are always trimmed
+    #[clap(long = "trim")]
+    trim: bool,
+    /// Fail if the input has more than this many records, before any are filtered out or selected with --head,
+    /// reporting how many it has
+    #[clap(long = "max-rows")]
+    max_rows: Option<usize>,
+    /// Fail if the input has no records at all, before any are filtered out, such as an empty file served by a mirror
+    #[clap(long = "fail-on-empty")]
+    fail_on_empty: bool,
+    /// Only output the first N records
+    #[clap(long = "head", conflicts_with_all = &["tail", "sample"])]
+    head: Option<usize>,
+    /// Only output the last N records
+    #[clap(long = "tail", conflicts_with = "sample")]
+    tail: Option<usize>,
+    /// Only output N records chosen at random, in the order of the input
+    #[clap(long = "sample")]
+    sample: Option<usize>,
+    /// Seed for choosing the records of --sample, so that the same seed always chooses the same records,
+    /// on every platform
+    #[clap(long = "seed", requires = "sample")]
+    seed: Option<u64>,
+    /// Sort the records by this field, as named in the output, e.g. elevation_ft.
+    /// Records where it's null come last
+    #[clap(long = "sort-by")]
+    sort_by: Option<String>,
+    /// Sort in descending order with --sort-by. Records where the field is null still come last
+    #[clap(long = "desc", requires = "sort-by")]
+    desc: bool,
+    /// Write an object mapping each distinct value of this field (e.g. airport_ident) to the records with it
+    #[clap(long = "group-by", conflicts_with_all = &["geojson", "split-by"])]
+    group_by: Option<String>,
+    /// With --group-by or --split-by, only keep the first N records of each group
+    #[clap(long = "limit-per-group")]
+    limit_per_group: Option<usize>,
+    /// Write one JSON file per distinct value of this field (e.g. iso_country) into the output directory,
+    /// or one .geojson file with --geojson
+    #[clap(long = "split-by", requires = "output-dir")]
+    split_by: Option<String>,
+    #[clap(long = "output-dir", parse(from_os_str), requires = "split-by")]
+    /// Directory to write the files of --split-by to
+    output_dir: Option<PathBuf>,
+    /// Only output the records added or changed since this earlier dump of the dataset, matched by `id`,
+    /// for a delta feed. The records removed since are logged, and written by --removed-output.
+    /// Not supported with --head, --tail or --sample, as every record must be read to know which were removed
+    #[clap(
+        long = "since",
+        parse(from_os_str),
+        conflicts_with_all = &["head", "tail", "sample"]
+    )]
+    since: Option<PathBuf>,
+    /// With --since, write a JSON array of the `id`s of the records removed since the earlier dump to this file
+    #[clap(long = "removed-output", parse(from_os_str), requires = "since")]
+    removed_output: Option<PathBuf>,
+}
 
-    // deserialize each record to a struct and add to list
-    for line in rdr.deserialize() {
-        let record: Airport = line?;
-        airport_list.push(record);
+/// The records of an earlier dump of a dataset, for keeping only the records changed since
+struct PreviousRecords {
+    /// The hash of each record, by id
+    hashes: HashMap<String, String>,
+}
+
+impl PreviousRecords {
+    /// Reads and parses an earlier dump of a dataset
+    fn read<T>(file_path: &Path, request_type: RequestType) -> Result<Self>
+    where
+        T: DeserializeOwned + Serialize,
+    {
+        let mut hashes = HashMap::new();
+        for record in parse_input_file::<T>(&Some(file_path.to_path_buf()), request_type)? {
+            let (id, hash) = id_and_hash(&record)?;
+            hashes.insert(id, hash);
+        }
+        Ok(PreviousRecords { hashes })
     }
 
-    // convert to json
-    if !pretty_print {
-        let json_out = serde_json::to_string(&airport_list)?;
-        Ok(json_out)
+    /// Returns whether a record was added or changed since the earlier dump.
+    /// Each record is forgotten once seen, so that the records left are the removed ones.
+    fn is_changed<T: Serialize>(&mut self, record: &T) -> Result<bool> {
+        let (id, hash) = id_and_hash(record)?;
+        Ok(self
+            .hashes
+            .remove(&id)
+            .is_none_or(|previous| previous != hash))
+    }
+
+    /// Returns the ids of the records removed since the earlier dump, sorted
+    fn removed_ids(self) -> Vec<String> {
+        let mut ids: Vec<String> = self.hashes.into_keys().collect();
+        ids.sort();
+        ids
+    }
+}
+
+/// Returns the `id` of a record and its hash, as for --with-hash
+fn id_and_hash<T: Serialize>(record: &T) -> Result<(String, String)> {
+    let value = serde_json::to_value(record)?;
+    let id = match value.get("id") {
+        Some(serde_json::Value::String(id)) => id.clone(),
+        _ => return Err(anyhow!("Record has no id")),
+    };
+    Ok((id, record_hash(&value)?))
+}
+
+/// Options for datasets with a `keywords` field
+#[derive(Clap)]
+struct KeywordOpts {
+    /// Separator between keywords in the `keywords` field
+    #[clap(long = "keyword-separator", default_value = ",")]
+    keyword_separator: char,
+}
+
+/// Options for the bundle command
+#[derive(Clap)]
+struct BundleOpts {
+    #[clap(long = "input-dir", parse(from_os_str))]
+    /// Directory or ZIP archive containing all of the data files from OurAirports, named as on the website. Downloaded if not given
+    input_dir: Option<PathBuf>,
+    #[clap(flatten)]
+    output: OutputOpts,
+    /// Compress the output with gzip
+    #[clap(short = 'z', long = "gzip")]
+    gzip: bool,
+    /// Fail if any dataset has more than this many records
+    #[clap(long = "max-rows")]
+    max_rows: Option<usize>,
+    /// Write each dataset to its own file in this directory instead, e.g. airports.json,
+    /// skipping the datasets whose file already exists so that a failed run can be resumed
+    #[clap(
+        long = "output-dir",
+        parse(from_os_str),
+        conflicts_with = "output-files"
+    )]
+    output_dir: Option<PathBuf>,
+    /// With --output-dir, write every dataset even if its file already exists
+    #[clap(long = "force", requires = "output-dir")]
+    force: bool,
+}
+
+/// Options for the validate command
+#[derive(Clap)]
+struct ValidateOpts {
+    #[clap(long = "input-dir", parse(from_os_str))]
+    /// Directory or ZIP archive containing all of the data files from OurAirports, named as on the website. Downloaded if not given
+    input_dir: Option<PathBuf>,
+    #[clap(flatten)]
+    output: OutputOpts,
+    /// Country code to accept even though it's not in the country data. Can be repeated
+    #[clap(long = "allow-country-code", number_of_values = 1)]
+    allow_country_code: Vec<String>,
+    /// How many degrees the two ends of a runway may be from exactly opposite
+    #[clap(long = "heading-tolerance", default_value = "5")]
+    heading_tolerance: f64,
+    /// Check that airport frequencies are in the civil aviation VHF band (118 to 136.975 MHz)
+    #[clap(long = "check-frequency-band")]
+    check_frequency_band: bool,
+    /// Check for airports far outside the bounds of their country, such as ones with the wrong country
+    /// or with their latitude and longitude swapped
+    #[clap(long = "check-country-location")]
+    check_country_location: bool,
+    /// Check for airports and navaids whose latitude and longitude look swapped,
+    /// with the two swapped back as suggested coordinates
+    #[clap(long = "check-swapped-coordinates")]
+    check_swapped_coordinates: bool,
+    /// Apply safe corrections to every data file and write the corrected files to this directory, named as on
+    /// the website, before checking them: trim whitespace, upper case codes, normalize runway headings to [0, 360)
+    /// and set blank booleans to false. The corrections are added to the report
+    #[clap(long = "fix", parse(from_os_str))]
+    fix: Option<PathBuf>,
+}
+
+/// Records wrapped together with their source and attribution
+#[derive(Serialize)]
+struct WithMetadata<'a, T: ?Sized> {
+    source: &'a Source,
+    attribution: &'static str,
+    license: &'static str,
+    /// The number of records, with --with-count
+    #[serde(skip_serializing_if = "Option::is_none")]
+    count: Option<usize>,
+    records: &'a T,
+}
+
+impl<'a, T: ?Sized> WithMetadata<'a, T> {
+    /// Wraps records with their source
+    fn new(source: &'a Source, records: &'a T) -> Self {
+        WithMetadata {
+            source,
+            attribution: ATTRIBUTION,
+            license: LICENSE,
+            count: None,
+            records,
+        }
+    }
+}
+
+/// Records wrapped together with how many there are
+#[derive(Serialize)]
+struct WithCount<'a, T> {
+    count: usize,
+    records: &'a [T],
+}
+
+/// Serializes a value to JSON, in the style given in the output options.
+/// `record_depth` is how deeply the records are nested in the value, for --pretty-compact
+fn to_json<T: Serialize + ?Sized>(
+    value: &T,
+    output: &OutputOpts,
+    record_depth: usize,
+) -> Result<String> {
+    if output.pretty_print {
+        serialize(value, PrettyFormatter::new(), output)
+    } else if output.pretty_sorted_keys {
+        let sorted = sort_keys(serde_json::to_value(value)?);
+        serialize(&sorted, PrettyFormatter::new(), output)
+    } else if output.pretty_compact {
+        serialize(value, RecordLinesFormatter::new(record_depth), output)
     } else {
-        let json_out = serde_json::to_string_pretty(&airport_list)?;
-        Ok(json_out)
+        serialize(value, CompactFormatter, output)
     }
 }
 
-/// Converts airport frequency data to JSON
-fn convert_airport_frequency_data(
-    file_path: &Option<std::path::PathBuf>,
-    pretty_print: bool,
+/// Serializes the value with the formatter, writing floats with a fixed number of decimals
+/// or compactly if requested in the output options
+fn serialize<T: Serialize + ?Sized, F: Formatter>(
+    value: &T,
+    formatter: F,
+    output: &OutputOpts,
 ) -> Result<String> {
-    let data = read_text(&file_path, RequestType::AirportFrequency)?;
-    eprintln!("Converting data");
-    let mut rdr = csv::Reader::from_reader(data.as_bytes());
+    match output.decimals {
+        Some(decimals) => Ok(to_string_with_formatter(
+            value,
+            FixedDecimalsFormatter::new(formatter, decimals),
+        )?),
+        None if output.compact_floats => Ok(to_string_with_formatter(
+            value,
+            CompactFloatsFormatter::new(formatter),
+        )?),
+        None => Ok(to_string_with_formatter(value, formatter)?),
+    }
+}
 
-    let mut airport_frequency_list: Vec<AirportFrequency> = Vec::new();
-    for line in rdr.deserialize() {
-        let record: AirportFrequency = line?;
-        airport_frequency_list.push(record);
+/// Returns an error with the number of records of csv data if it's more than the maximum.
+/// The rows are counted without parsing them into records, before any is kept, so that --head doesn't stop early
+fn check_max_rows(data: &str, trim: bool, max_rows: Option<usize>) -> Result<()> {
+    let max_rows = match max_rows {
+        Some(max_rows) => max_rows,
+        None => return Ok(()),
+    };
+    let record_count = non_blank_rows(csv_reader(data, trim)).count();
+    if record_count > max_rows {
+        Err(Error::Validation(format!(
+            "input has {} records, more than the maximum of {}",
+            record_count, max_rows
+        ))
+        .into())
+    } else {
+        Ok(())
     }
+}
 
-    if !pretty_print {
-        let json_out = serde_json::to_string(&airport_frequency_list)?;
-        Ok(json_out)
+/// Returns an error if csv data has no records, only a header and blank lines or nothing at all
+fn check_not_empty(data: &str, trim: bool) -> Result<()> {
+    let has_records = non_blank_rows(csv_reader(data, trim)).next().is_some();
+    if has_records {
+        Ok(())
     } else {
-        let json_out = serde_json::to_string_pretty(&airport_frequency_list)?;
-        Ok(json_out)
+        Err(Error::Validation("input has no records".to_string()).into())
     }
 }
 
-/// Converts runway data to JSON
-fn convert_runway_data(
-    file_path: &Option<std::path::PathBuf>,
-    pretty_print: bool,
+/// Returns the URL to download the csv data from: the URL if given, otherwise the default URL without an input file
+fn input_url(input: &InputOpts, request_type: RequestType) -> Option<&str> {
+    match (&input.from_url, &input.input_file) {
+        (Some(url), _) => Some(url.as_str()),
+        (None, None) => Some(request_type.url()),
+        (None, Some(_)) => None,
+    }
+}
+
+/// Reads the csv data from the URL if given, otherwise from the input file or the default URL
+fn read_input(input: &InputOpts, request_type: RequestType) -> Result<(String, Source)> {
+    if let Some(url) = input_url(input, request_type) {
+        let (data, source) = block_on(download_text_limited(url, input.max_bytes))?;
+        check_columns(data.as_bytes(), request_type)?;
+        Ok((data, source))
+    } else {
+        block_on(read_text(&input.input_file, request_type))
+    }
+}
+
+/// Where the records of a conversion are parsed from
+enum Input<T> {
+    /// The whole csv data, read from a file or downloaded before any of it is parsed
+    Text(String),
+    /// Records parsed while the data is downloaded, on a runtime of their own
+    Download(tokio::runtime::Runtime, RecordStream<T>),
+}
+
+impl<T> Input<T>
+where
+    T: DeserializeOwned,
+{
+    /// Parses the records of the input one at a time, together with the line of the data each record starts on.
+    /// Downloaded records have no line, as it's only needed with --with-line, which reads the whole data first.
+    fn records<'a>(&'a mut self, opts: &ConvertOpts) -> Result<Records<'a, T>>
+    where
+        T: 'a,
+    {
+        Ok(match self {
+            Input::Text(data) => {
+                if opts.fail_on_empty {
+                    check_not_empty(data, opts.trim)?;
+                }
+                Box::new(parse_records(data, opts.max_rows, opts.trim)?)
+            }
+            Input::Download(runtime, records) => {
+                info!("Converting data as it's downloaded");
+                Box::new(iter::from_fn(move || {
+                    let record = runtime.block_on(records.next())?;
+                    Some(record.map(|record| (0, record)).map_err(Into::into))
+                }))
+            }
+        })
+    }
+}
+
+impl ConvertOpts {
+    /// Returns whether the records can be parsed while the data is downloaded,
+    /// as none of the options need the whole data before parsing it
+    fn streams_download(&self) -> bool {
+        self.input.max_bytes.is_none()
+            && self.max_rows.is_none()
+            && self.cache_dir.is_none()
+            && !self.fail_on_empty
+            && !self.trim
+            && !self.with_line
+    }
+}
+
+/// Reads the input of a conversion as for `read_input`, except that when downloading, the records are parsed
+/// from the response body as it arrives if the options allow
+fn read_records_input<T>(
+    opts: &ConvertOpts,
+    request_type: RequestType,
+) -> Result<(Input<T>, Source)>
+where
+    T: DeserializeOwned,
+{
+    match input_url(&opts.input, request_type) {
+        Some(url) if opts.streams_download() => {
+            let runtime = runtime()?;
+            let (records, source) = runtime.block_on(download_records(url, request_type))?;
+            Ok((Input::Download(runtime, records), source))
+        }
+        _ => {
+            let (data, source) = read_input(&opts.input, request_type)?;
+            Ok((Input::Text(data), source))
+        }
+    }
+}
+
+/// Returns a runtime for the downloads and reads of the library, as the rest of the converter is synchronous
+fn runtime() -> Result<tokio::runtime::Runtime> {
+    Ok(tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?)
+}
+
+/// Runs a download or read of the library to completion on a runtime of its own
+fn block_on<T>(future: impl Future<Output = std::result::Result<T, Error>>) -> Result<T> {
+    Ok(runtime()?.block_on(future)?)
+}
+
+/// How many records to parse between progress updates
+const PROGRESS_INTERVAL: usize = 10_000;
+
+/// Returns a csv reader of the data, trimming the whitespace around every field if `trim`
+fn csv_reader(data: &str, trim: bool) -> csv::Reader<&[u8]> {
+    csv::ReaderBuilder::new()
+        .trim(if trim {
+            csv::Trim::All
+        } else {
+            csv::Trim::None
+        })
+        .from_reader(data.as_bytes())
+}
+
+/// Parses the records of csv data of any type one at a time,
+/// together with the line of the data each record starts on, once it has been checked against --max-rows
+fn parse_records<'a, T>(
+    data: &'a str,
+    max_rows: Option<usize>,
+    trim: bool,
+) -> Result<impl Iterator<Item = Result<(u64, T)>> + 'a>
+where
+    T: DeserializeOwned + 'a,
+{
+    check_max_rows(data, trim, max_rows)?;
+    info!("Converting data");
+    let mut rdr = csv_reader(data, trim);
+    let headers = rdr.headers().map_err(Error::Parse)?.clone();
+    // progress is only useful to someone watching
+    let show_progress = io::stderr().is_terminal();
+
+    Ok(non_blank_rows(rdr).enumerate().map(move |(index, row)| {
+        let row = row?;
+        let line = row.position().map_or(0, csv::Position::line);
+        let record: T = row.deserialize(Some(&headers)).map_err(Error::from)?;
+        let record_count = index + 1;
+        if show_progress && record_count.is_multiple_of(PROGRESS_INTERVAL) {
+            info!("Converted {} records", record_count);
+        }
+        Ok((line, record))
+    }))
+}
+
+/// Parses every record of csv data of any type
+fn parse_data<T>(data: &str, max_rows: Option<usize>) -> Result<Vec<T>>
+where
+    T: DeserializeOwned,
+{
+    parse_records(data, max_rows, false)?
+        .map(|record| record.map(|(_, record)| record))
+        .collect()
+}
+
+/// Reads and parses every record of a data file, or downloads it if not given
+fn parse_input_file<T>(file_path: &Option<PathBuf>, request_type: RequestType) -> Result<Vec<T>>
+where
+    T: DeserializeOwned,
+{
+    let (data, _) = block_on(read_text(file_path, request_type))?;
+    parse_data(&data, None)
+}
+
+/// Reads and parses every record of an additional data file, if given
+fn parse_optional_file<T>(
+    file_path: &Option<PathBuf>,
+    request_type: RequestType,
+) -> Result<Option<Vec<T>>>
+where
+    T: DeserializeOwned,
+{
+    file_path
+        .as_ref()
+        .map(|_| parse_input_file(file_path, request_type))
+        .transpose()
+}
+
+/// Reads, parses and transforms every record of data of any type,
+/// keeping the records for which `transform` returns true, sorted with --sort-by,
+/// and only the first or last of them with --head or --tail, or some of them with --sample.
+/// Each record is paired with the line of the data it starts on.
+fn load_records<T>(
+    opts: &ConvertOpts,
+    request_type: RequestType,
+    transform: impl FnMut(&mut T) -> bool,
+    finish: Finish<T>,
+) -> Result<(Vec<(u64, T)>, Source)>
+where
+    T: DeserializeOwned + Serialize,
+{
+    // read original file as csv
+    let (mut input, source) = read_records_input(opts, request_type)?;
+    Ok((select_records(opts, &mut input, transform, finish)?, source))
+}
+
+/// Reworks the records kept by a transform as a whole, such as merging duplicates, before --head, --tail or --sample
+type Finish<T> = Option<fn(Vec<(u64, T)>) -> Vec<(u64, T)>>;
+
+/// Parses and transforms the records of the input one at a time, keeping the records for which `transform` returns true
+fn filter_records<'a, T>(
+    opts: &ConvertOpts,
+    input: &'a mut Input<T>,
+    mut transform: impl FnMut(&mut T) -> bool + 'a,
+) -> Result<impl Iterator<Item = Result<(u64, T)>> + 'a>
+where
+    T: DeserializeOwned + 'a,
+{
+    Ok(input.records(opts)?.filter_map(move |record| {
+        record
+            .map(|(line, mut record)| transform(&mut record).then_some((line, record)))
+            .transpose()
+    }))
+}
+
+/// Parses and transforms every record of the input, as for `load_records`
+fn select_records<T>(
+    opts: &ConvertOpts,
+    input: &mut Input<T>,
+    transform: impl FnMut(&mut T) -> bool,
+    finish: Finish<T>,
+) -> Result<Vec<(u64, T)>>
+where
+    T: DeserializeOwned + Serialize,
+{
+    let records = filter_records(opts, input, transform)?;
+    let records: Records<T> = match (finish, &opts.sort_by) {
+        (None, None) => Box::new(records),
+        (finish, sort_by) => {
+            let mut record_list = records.collect::<Result<_>>()?;
+            if let Some(finish) = finish {
+                record_list = finish(record_list);
+            }
+            if let Some(field) = sort_by {
+                record_list = sort_by_field(record_list, field, opts.desc, |(_, record)| record)?;
+            }
+            Box::new(record_list.into_iter().map(Ok))
+        }
+    };
+    let record_list = match (opts.head, opts.tail) {
+        (Some(head), _) => records.take(head).collect::<Result<_>>()?,
+        (None, Some(tail)) => {
+            // only keep the last records in memory
+            let mut last_records = VecDeque::with_capacity(tail);
+            for record in records {
+                // checked even with --tail 0, so that parse errors aren't skipped
+                let record = record?;
+                if last_records.len() == tail {
+                    last_records.pop_front();
+                }
+                if tail > 0 {
+                    last_records.push_back(record);
+                }
+            }
+            last_records.into()
+        }
+        (None, None) => match opts.sample {
+            Some(sample) => sample_records(records, sample, opts.seed.unwrap_or_default())?,
+            None => records.collect::<Result<_>>()?,
+        },
+    };
+    Ok(record_list)
+}
+
+/// Chooses `sample` of the records at random with a seeded generator, keeping them in their original order.
+/// Only the chosen records are kept in memory. The generator is ChaCha8 and the records are drawn as `u64`s,
+/// so that a seed chooses the same records on every platform and with every version of rand.
+fn sample_records<T>(
+    records: impl Iterator<Item = Result<(u64, T)>>,
+    sample: usize,
+    seed: u64,
+) -> Result<Vec<(u64, T)>> {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    // reservoir sampling: the nth record replaces a chosen one with probability sample / n
+    let mut chosen: Vec<(usize, (u64, T))> = Vec::with_capacity(sample);
+    for (index, record) in records.enumerate() {
+        let record = record?;
+        if chosen.len() < sample {
+            chosen.push((index, record));
+        } else {
+            let replaced = rng.gen_range(0..=index as u64);
+            if replaced < sample as u64 {
+                chosen[replaced as usize] = (index, record);
+            }
+        }
+    }
+    chosen.sort_by_key(|(index, _)| *index);
+    Ok(chosen.into_iter().map(|(_, record)| record).collect())
+}
+
+impl ConvertOpts {
+    /// Returns whether the records need to be reshaped as JSON values before output
+    fn shapes_records(&self) -> bool {
+        self.with_hash
+            || self.with_line
+            || !self.compute.is_empty()
+            || !self.fields.is_empty()
+            || !self.exclude_fields.is_empty()
+            || self.geojson
+            || self.nest_coords
+            || self.group_by.is_some()
+            || self.format != Format::Json
+    }
+}
+
+/// Converts a record to a JSON value, adding the fields requested in the options
+fn shape_record<T>(opts: &ConvertOpts, line: u64, record: &T) -> Result<serde_json::Value>
+where
+    T: Serialize,
+{
+    let mut value = serde_json::to_value(record)?;
+    if let serde_json::Value::Object(fields) = &mut value {
+        for field in &opts.compute {
+            let computed = field.compute(fields).unwrap_or_else(|err| {
+                let id = fields
+                    .get("id")
+                    .and_then(|id| id.as_str())
+                    .unwrap_or_default();
+                warn!(
+                    "Could not compute {} for record {}: {}",
+                    field.name, id, err
+                );
+                serde_json::Value::Null
+            });
+            fields.insert(field.name.clone(), computed);
+        }
+        if opts.nest_coords {
+            *fields = nest_coordinates(mem::take(fields));
+        }
+        if opts.with_hash {
+            fields.insert("_hash".to_string(), record_hash(record)?.into());
+        }
+        if opts.with_line {
+            fields.insert("_source_line".to_string(), line.into());
+        }
+        if !opts.fields.is_empty() || !opts.exclude_fields.is_empty() {
+            *fields = mem::take(fields)
+                .into_iter()
+                .filter(|(key, _)| {
+                    (opts.fields.is_empty() || opts.fields.contains(key))
+                        && !opts.exclude_fields.contains(key)
+                })
+                .collect();
+        }
+    }
+    Ok(value)
+}
+
+/// Converts records of any type to the output format
+fn format_records<T>(
+    opts: &ConvertOpts,
+    request_type: RequestType,
+    source: &Source,
+    record_list: &[(u64, T)],
+) -> Result<String>
+where
+    T: Serialize,
+{
+    if !opts.shapes_records() {
+        let records: Vec<&T> = record_list.iter().map(|(_, record)| record).collect();
+        return wrap_records_to_json(opts, source, &records);
+    }
+    let mut shaped_list = record_list
+        .iter()
+        .map(|(line, record)| shape_record(opts, *line, record))
+        .collect::<Result<Vec<_>>>()?;
+    let array_delimiter = Some(opts.flatten_delimiter.as_str()).filter(|_| opts.flatten_keywords);
+    let delimiter = match opts.format {
+        Format::Json if opts.geojson => {
+            if let RequestType::Navaid = request_type {
+                for record in &mut shaped_list {
+                    if let serde_json::Value::Object(fields) = record {
+                        add_navaid_style(fields);
+                    }
+                }
+            }
+            let mut collection = feature_collection(&shaped_list);
+            if opts.with_metadata {
+                collection["properties"] = serde_json::json!({
+                    "source": source,
+                    "attribution": ATTRIBUTION,
+                    "license": LICENSE,
+                });
+            }
+            return to_json(&collection, &opts.output, 2);
+        }
+        Format::Json => {
+            return match &opts.group_by {
+                Some(field) => group_records_to_json(opts, source, shaped_list, field),
+                None => wrap_records_to_json(opts, source, &shaped_list),
+            }
+        }
+        Format::Topojson => {
+            return to_json(
+                &topology(request_type.name(), &shaped_list),
+                &opts.output,
+                4,
+            )
+        }
+        Format::Tuples => return to_json(&to_tuples(&shaped_list), &opts.output, 2),
+        Format::JsonlGz => return to_json_lines(opts, &shaped_list),
+        Format::Bson => unreachable!("BSON is only written by write_bson"),
+        Format::Flatgeobuf => unreachable!("FlatGeobuf is only written by write_flatgeobuf"),
+        Format::Sql => {
+            let table = opts.table.as_deref().unwrap_or_else(|| request_type.name());
+            let script = to_sql(&shaped_list, table, array_delimiter)?;
+            return Ok(script.trim_end_matches('\n').to_string());
+        }
+        Format::Csv => b',',
+        Format::Tsv => b'\t',
+    };
+    let table = to_delimited(&shaped_list, delimiter, array_delimiter)?;
+    // the output is written with a newline at the end, as for JSON
+    Ok(table.trim_end_matches('\n').to_string())
+}
+
+/// Serializes a record to JSON on a single line, for --format jsonl-gz
+fn to_json_line(opts: &ConvertOpts, record: &serde_json::Value) -> Result<String> {
+    serialize(record, CompactFormatter, &opts.output)
+}
+
+/// Serializes records to newline-delimited JSON, with one record per line
+fn to_json_lines(opts: &ConvertOpts, record_list: &[serde_json::Value]) -> Result<String> {
+    let lines = record_list
+        .iter()
+        .map(|record| to_json_line(opts, record))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(lines.join("\n"))
+}
+
+/// Records parsed one at a time, each paired with the line of the data it starts on
+type Records<'a, T> = Box<dyn Iterator<Item = Result<(u64, T)>> + 'a>;
+
+/// Parses and transforms the records of the input one at a time, as for `select_records`.
+/// Only --tail, --sample, --sort-by and `finish` keep records in memory, as they can't know which records to keep
+/// until the end.
+fn stream_records<'a, T>(
+    opts: &ConvertOpts,
+    input: &'a mut Input<T>,
+    transform: impl FnMut(&mut T) -> bool + 'a,
+    finish: Finish<T>,
+) -> Result<Records<'a, T>>
+where
+    T: DeserializeOwned + Serialize + 'a,
+{
+    Ok(match (opts.head, opts.tail, finish) {
+        (Some(head), _, None) if opts.sort_by.is_none() => {
+            Box::new(filter_records(opts, input, transform)?.take(head))
+        }
+        (None, None, None) if opts.sample.is_none() && opts.sort_by.is_none() => {
+            Box::new(filter_records(opts, input, transform)?)
+        }
+        _ => Box::new(
+            select_records(opts, input, transform, finish)?
+                .into_iter()
+                .map(Ok),
+        ),
+    })
+}
+
+/// Returns a writer to the output file if given, otherwise to stdout
+fn output_writer(output_file: Option<&Path>) -> Result<Box<dyn Write>> {
+    Ok(match output_file {
+        Some(output_path) => Box::new(io::BufWriter::new(fs::File::create(output_path)?)),
+        None => Box::new(io::stdout()),
+    })
+}
+
+/// A writer that counts the bytes written through it, failing once they would be more than --max-output-bytes
+struct SizedWriter<W> {
+    writer: W,
+    written: usize,
+    max_bytes: Option<usize>,
+}
+
+impl<W: Write> SizedWriter<W> {
+    fn new(writer: W, max_bytes: Option<usize>) -> Self {
+        SizedWriter {
+            writer,
+            written: 0,
+            max_bytes,
+        }
+    }
+
+    /// Flushes the writer and reports how many bytes were written
+    fn finish(mut self) -> Result<()> {
+        self.writer.flush()?;
+        debug!("Wrote {} bytes", self.written);
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for SizedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Some(max_bytes) = self.max_bytes {
+            if self.written + buf.len() > max_bytes {
+                return Err(io::Error::other(output_too_large(max_bytes)));
+            }
+        }
+        let written = self.writer.write(buf)?;
+        self.written += written;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Returns the error when the output would be larger than --max-output-bytes
+fn output_too_large(max_bytes: usize) -> String {
+    format!(
+        "the output would be larger than --max-output-bytes {}",
+        max_bytes
+    )
+}
+
+/// Fails if output of `len` bytes is larger than --max-output-bytes, so that none of it is written
+fn check_output_size(opts: &ConvertOpts, len: usize) -> Result<()> {
+    match opts.max_output_bytes {
+        Some(max_bytes) if len > max_bytes => Err(anyhow!(output_too_large(max_bytes))),
+        _ => Ok(()),
+    }
+}
+
+/// Writes the output of a converter as for `write_output`, unless it's larger than --max-output-bytes.
+/// Its size is the bytes written, so on stdout it includes the \n that `write_output` ends it with,
+/// as it would be counted if streamed
+fn write_converted_output(opts: &ConvertOpts, json_out: String) -> Result<()> {
+    let output_file = opts.output.output_file()?;
+    let len = json_out.len() + usize::from(output_file.is_none());
+    check_output_size(opts, len)?;
+    write_output(output_file, json_out)?;
+    debug!("Wrote {} bytes", len);
+    Ok(())
+}
+
+/// Writes the binary output of a converter as for `write_binary_output`, unless it's larger than --max-output-bytes
+fn write_converted_binary(opts: &ConvertOpts, data: &[u8]) -> Result<()> {
+    check_output_size(opts, data.len())?;
+    write_binary_output(opts.output.output_file()?, data)?;
+    debug!("Wrote {} bytes", data.len());
+    Ok(())
+}
+
+/// Returns a writer to the output of --format jsonl-gz, appending to the output file with --append.
+/// The records appended are a gzip member of their own, which `zcat` and `gzip -d` read as part of the same file
+fn jsonl_gz_writer(opts: &ConvertOpts) -> Result<Box<dyn Write>> {
+    match opts.output.output_file()? {
+        Some(output_path) if opts.append => Ok(Box::new(io::BufWriter::new(
+            fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(output_path)?,
+        ))),
+        output_file => output_writer(output_file),
+    }
+}
+
+/// Writes the output of --format jsonl-gz with `write`. If that fails, a file given with --append is truncated
+/// back to its length before, so that it isn't left ending in part of a gzip member
+fn with_jsonl_gz_writer(
+    opts: &ConvertOpts,
+    write: impl FnOnce(Box<dyn Write>) -> Result<()>,
+) -> Result<()> {
+    let appended_file = match opts.output.output_file()? {
+        Some(output_path) if opts.append => {
+            let len = match fs::metadata(output_path) {
+                Ok(metadata) => metadata.len(),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => 0,
+                Err(err) => return Err(err.into()),
+            };
+            Some((output_path, len))
+        }
+        _ => None,
+    };
+    let result = write(jsonl_gz_writer(opts)?);
+    if let (Err(_), Some((output_path, len))) = (&result, appended_file) {
+        fs::OpenOptions::new()
+            .write(true)
+            .open(output_path)?
+            .set_len(len)?;
+    }
+    result
+}
+
+/// Converts data of any type to newline-delimited JSON compressed with gzip,
+/// writing each record to the output as soon as it's parsed
+fn write_jsonl_gz<T>(
+    opts: &ConvertOpts,
+    request_type: RequestType,
+    transform: impl FnMut(&mut T) -> bool,
+    finish: Finish<T>,
+) -> Result<()>
+where
+    T: DeserializeOwned + Serialize,
+{
+    let (mut input, _) = read_records_input(opts, request_type)?;
+    let records = stream_records(opts, &mut input, transform, finish)?;
+    with_jsonl_gz_writer(opts, |writer| {
+        let mut encoder = GzEncoder::new(
+            SizedWriter::new(writer, opts.max_output_bytes),
+            Compression::default(),
+        );
+        for record in records {
+            let (line, record) = record?;
+            let json_line = to_json_line(opts, &shape_record(opts, line, &record)?)?;
+            encoder.write_all(json_line.as_bytes())?;
+            encoder.write_all(b"\n")?;
+        }
+        encoder.finish()?.finish()
+    })
+}
+
+/// Converts data of any type to a stream of BSON documents, one per record, as read by `mongorestore`,
+/// writing each record to the output as soon as it's parsed
+#[cfg(feature = "bson")]
+fn write_bson<T>(
+    opts: &ConvertOpts,
+    request_type: RequestType,
+    transform: impl FnMut(&mut T) -> bool,
+    finish: Finish<T>,
+) -> Result<()>
+where
+    T: DeserializeOwned + Serialize,
+{
+    let (mut input, _) = read_records_input(opts, request_type)?;
+    let records = stream_records(opts, &mut input, transform, finish)?;
+    let mut writer = SizedWriter::new(
+        output_writer(opts.output.output_file()?)?,
+        opts.max_output_bytes,
+    );
+    for record in records {
+        let (line, record) = record?;
+        if let serde_json::Value::Object(fields) = shape_record(opts, line, &record)? {
+            to_document(fields).to_writer(&mut writer)?;
+        }
+    }
+    writer.finish()
+}
+
+/// Fails, as BSON output needs the `bson` feature
+#[cfg(not(feature = "bson"))]
+fn write_bson<T>(
+    _opts: &ConvertOpts,
+    _request_type: RequestType,
+    _transform: impl FnMut(&mut T) -> bool,
+    _finish: Finish<T>,
+) -> Result<()> {
+    Err(anyhow!(
+        "--format bson is only supported when built with the bson feature"
+    ))
+}
+
+/// Converts airports or navaids to a FlatGeobuf file of points with a spatial index.
+/// Every record is kept in memory, as the features are ordered by their location before any is written
+fn write_flatgeobuf<T>(
+    opts: &ConvertOpts,
+    request_type: RequestType,
+    transform: impl FnMut(&mut T) -> bool,
+    finish: Finish<T>,
+) -> Result<()>
+where
+    T: DeserializeOwned + Serialize,
+{
+    let (record_list, _) = load_records(opts, request_type, transform, finish)?;
+    write_converted_binary(opts, &flatgeobuf_bytes(opts, request_type, &record_list)?)
+}
+
+/// Encodes airports or navaids as a FlatGeobuf file
+#[cfg(feature = "flatgeobuf")]
+fn flatgeobuf_bytes<T>(
+    opts: &ConvertOpts,
+    request_type: RequestType,
+    record_list: &[(u64, T)],
+) -> Result<Vec<u8>>
+where
+    T: Serialize,
+{
+    let shaped_list = record_list
+        .iter()
+        .map(|(line, record)| shape_record(opts, *line, record))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(to_flatgeobuf(request_type.name(), &shaped_list)?)
+}
+
+/// Fails, as FlatGeobuf output needs the `flatgeobuf` feature
+#[cfg(not(feature = "flatgeobuf"))]
+fn flatgeobuf_bytes<T>(
+    _opts: &ConvertOpts,
+    _request_type: RequestType,
+    _record_list: &[(u64, T)],
+) -> Result<Vec<u8>> {
+    Err(anyhow!(
+        "--format flatgeobuf is only supported when built with the flatgeobuf feature"
+    ))
+}
+
+/// Adds a record to a group, unless the group already has `limit` records
+fn push_limited<T>(group: &mut Vec<T>, record: T, limit: Option<usize>) {
+    if limit.is_none_or(|limit| group.len() < limit) {
+        group.push(record);
+    }
+}
+
+/// Converts records to a JSON object mapping each distinct value of the field to the records with it,
+/// wrapped with their source if requested
+fn group_records_to_json(
+    opts: &ConvertOpts,
+    source: &Source,
+    record_list: Vec<serde_json::Value>,
+    field: &str,
 ) -> Result<String> {
-    let data = read_text(&file_path, RequestType::Runway)?;
-    eprintln!("Converting data");
-    let mut rdr = csv::Reader::from_reader(data.as_bytes());
+    let mut groups: BTreeMap<String, Vec<serde_json::Value>> = BTreeMap::new();
+    for record in record_list {
+        let key = split_key(&record, field)?;
+        push_limited(groups.entry(key).or_default(), record, opts.limit_per_group);
+    }
 
-    let mut runway_list: Vec<Runway> = Vec::new();
-    for line in rdr.deserialize() {
-        let record: Runway = line?;
-        runway_list.push(record);
+    if opts.with_metadata {
+        let wrapped = WithMetadata::new(source, &groups);
+        to_json(&wrapped, &opts.output, 3)
+    } else {
+        to_json(&groups, &opts.output, 2)
     }
+}
 
-    if !pretty_print {
-        let json_out = serde_json::to_string(&runway_list)?;
-        Ok(json_out)
+/// Converts records of any type to JSON, wrapped with their source or count if requested.
+/// The records are all selected before any are written, so the count always comes first.
+fn wrap_records_to_json<T>(opts: &ConvertOpts, source: &Source, record_list: &[T]) -> Result<String>
+where
+    T: Serialize,
+{
+    if opts.with_metadata {
+        let mut wrapped = WithMetadata::new(source, record_list);
+        if opts.with_count {
+            wrapped.count = Some(record_list.len());
+        }
+        to_json(&wrapped, &opts.output, 2)
+    } else if opts.with_count {
+        let wrapped = WithCount {
+            count: record_list.len(),
+            records: record_list,
+        };
+        to_json(&wrapped, &opts.output, 2)
     } else {
-        let json_out = serde_json::to_string_pretty(&runway_list)?;
-        Ok(json_out)
+        to_json(record_list, &opts.output, 1)
     }
 }
 
-/// Converts navaid data to JSON
-fn convert_navaid_data(
-    file_path: &Option<std::path::PathBuf>,
-    pretty_print: bool,
-) -> Result<String> {
-    let data = read_text(&file_path, RequestType::Navaid)?;
-    eprintln!("Converting data");
-    let mut rdr = csv::Reader::from_reader(data.as_bytes());
+/// Converts data of any type to JSON, transforming and filtering each record after parsing
+fn convert_data<T>(
+    opts: &ConvertOpts,
+    request_type: RequestType,
+    transform: impl FnMut(&mut T) -> bool,
+    finish: Finish<T>,
+) -> Result<String>
+where
+    T: DeserializeOwned + Serialize,
+{
+    let (mut input, source) = read_records_input(opts, request_type)?;
+    // the input is read in full with --cache-dir, so that it can be hashed
+    let cache_file = match (&opts.cache_dir, &input) {
+        (Some(cache_dir), Input::Text(data)) => Some(cache_dir.join(cache_key(opts, data))),
+        _ => None,
+    };
+    let cached = cache_file.as_ref().and_then(|cache_file| {
+        let record_list = cached_records(&fs::read_to_string(cache_file).ok()?).ok()?;
+        info!("Using cached records {}", cache_file.to_string_lossy());
+        Some(record_list)
+    });
+
+    let record_list = match cached {
+        Some(record_list) => record_list,
+        None => {
+            let record_list = select_records(opts, &mut input, transform, finish)?;
+            if let (Some(cache_dir), Some(cache_file)) = (&opts.cache_dir, &cache_file) {
+                fs::create_dir_all(cache_dir)?;
+                fs::write(cache_file, serde_json::to_string(&record_list)?)?;
+            }
+            record_list
+        }
+    };
+    format_records(opts, request_type, &source, &record_list)
+}
+
+/// Reads the records cached by an earlier conversion, with the line each starts on
+fn cached_records<T: DeserializeOwned>(cached: &str) -> Result<Vec<(u64, T)>> {
+    let values: Vec<(u64, serde_json::Value)> = serde_json::from_str(cached)?;
+    values
+        .into_iter()
+        .map(|(line, value)| Ok((line, from_json_value(value)?)))
+        .collect()
+}
+
+/// Options that only say where the input is read from or the output is written to, with whether they take a value.
+/// They don't change the records, so they are left out of the cache key
+const CACHE_IGNORED_OPTIONS: &[(&str, bool)] = &[
+    ("-o", true),
+    ("--output", true),
+    ("--cache-dir", true),
+    ("--from-url", true),
+    ("--fields-file", true),
+    ("--max-output-bytes", true),
+    ("--append", false),
+];
+
+/// Returns the name of the cached records of a conversion: a SHA-256 hash of the version,
+/// the options of the command line that change the records, the selected fields (which may be read
+/// from --fields-file) and the input data, so that changing any of them misses the cache.
+/// The records are cached before they are written, so the same conversion written to another file,
+/// or in another format, reuses them, and --with-metadata is written afresh
+fn cache_key(opts: &ConvertOpts, data: &str) -> String {
+    let input_file = opts.input.input_file.as_ref().map(|path| path.as_os_str());
+    let mut hasher = Sha256::new();
+    hasher.update(env!("CARGO_PKG_VERSION"));
+    let mut args = env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        let text = arg.to_string_lossy();
+        // as --name value, --name=value or, for a short option, -nvalue
+        let ignored = CACHE_IGNORED_OPTIONS.iter().find(|(name, _)| {
+            text == *name
+                || text.starts_with(&format!("{}=", name))
+                || (!name.starts_with("--") && text.starts_with(name))
+        });
+        match ignored {
+            // the value is the next argument
+            Some((name, true)) if text == *name => {
+                args.next();
+            }
+            Some(_) => {}
+            None if Some(arg.as_os_str()) == input_file => {}
+            None => {
+                hasher.update(b"\0");
+                hasher.update(text.as_bytes());
+            }
+        }
+    }
+    for field in &opts.fields {
+        hasher.update(b"\0");
+        hasher.update(field.as_bytes());
+    }
+    hasher.update(b"\0");
+    hasher.update(data);
+    format!("{:x}", hasher.finalize())
+}
 
-    let mut navaid_list: Vec<Navaid> = Vec::new();
-    for line in rdr.deserialize() {
-        let record: Navaid = line?;
-        navaid_list.push(record);
+/// Turns off the cache for conversions that also read other datasets, which aren't part of the cache key
+fn without_cache(mut opts: ConvertOpts) -> ConvertOpts {
+    if opts.cache_dir.take().is_some() {
+        warn!("Not using the cache, as the conversion also reads other datasets");
     }
+    opts
+}
 
-    if !pretty_print {
-        let json_out = serde_json::to_string(&navaid_list)?;
-        Ok(json_out)
+/// Returns the value of a field of a record as a string, for splitting the output by it
+fn split_key(record: &serde_json::Value, field: &str) -> Result<String> {
+    match record.get(field) {
+        Some(serde_json::Value::String(value)) => Ok(value.clone()),
+        Some(serde_json::Value::Null) => Ok(String::new()),
+        Some(value @ (serde_json::Value::Number(_) | serde_json::Value::Bool(_))) => {
+            Ok(value.to_string())
+        }
+        Some(_) => Err(anyhow!("Can not group by field {}", field)),
+        None => Err(anyhow!("No field named {}", field)),
+    }
+}
+
+/// Writes the records to one JSON file per distinct value of a field,
+/// named after the value. Records where the field is empty are written to `_.json`.
+/// With --geojson, groups without any record with coordinates have no file, as their collection would be empty.
+fn write_split<T>(
+    opts: &ConvertOpts,
+    request_type: RequestType,
+    source: &Source,
+    record_list: Vec<(u64, T)>,
+    field: &str,
+    output_dir: &Path,
+) -> Result<()>
+where
+    T: Serialize,
+{
+    let mut groups: BTreeMap<String, Vec<(u64, T)>> = BTreeMap::new();
+    for (line, record) in record_list {
+        let key = split_key(&serde_json::to_value(&record)?, field)?;
+        push_limited(
+            groups.entry(key).or_default(),
+            (line, record),
+            opts.limit_per_group,
+        );
+    }
+
+    let extension = if opts.geojson {
+        "geojson"
     } else {
-        let json_out = serde_json::to_string_pretty(&navaid_list)?;
-        Ok(json_out)
+        opts.format.extension()
+    };
+    if opts.geojson {
+        groups.retain(|_, group| {
+            group.iter().any(|(_, record)| {
+                serde_json::to_value(record)
+                    .is_ok_and(|record| point_coordinates(&record).is_some())
+            })
+        });
     }
+
+    fs::create_dir_all(output_dir)?;
+    for (key, group) in &groups {
+        let file_name = match key.as_str() {
+            "" => "_".to_string(),
+            key => key.replace(['/', '\\'], "_"),
+        };
+        let output_path = output_dir.join(format!("{}.{}", file_name, extension));
+        let out = format_records(opts, request_type, source, group)?;
+        if opts.format == Format::JsonlGz {
+            fs::write(output_path, gzip(format!("{}\n", out).as_bytes())?)?;
+        } else {
+            fs::write(output_path, out)?;
+        }
+    }
+    info!(
+        "Wrote {} files to {}",
+        groups.len(),
+        output_dir.to_string_lossy()
+    );
+    Ok(())
 }
 
-/// Converts country data to JSON
-fn convert_country_data(
-    file_path: &Option<std::path::PathBuf>,
-    pretty_print: bool,
-) -> Result<String> {
-    let data = read_text(&file_path, RequestType::Country)?;
-    eprintln!("Converting data");
-    let mut rdr = csv::Reader::from_reader(data.as_bytes());
+/// Reads and parses every record of one dataset from the input directory, or downloads it
+fn parse_dataset<T>(
+    input_dir: &Option<PathBuf>,
+    request_type: RequestType,
+    max_rows: Option<usize>,
+) -> Result<Vec<T>>
+where
+    T: DeserializeOwned,
+{
+    let input_file = input_dir
+        .as_ref()
+        .map(|dir| dataset_file(dir, request_type));
+    let (data, _) = block_on(read_text(&input_file, request_type))?;
+    parse_data(&data, max_rows)
+}
+
+/// Runs the work for one dataset on its own thread, with the keyword separator of the command
+fn spawn_dataset<'scope, R: Send + 'scope>(
+    scope: &'scope thread::Scope<'scope, '_>,
+    keyword_separator: char,
+    work: impl FnOnce() -> Result<R> + Send + 'scope,
+) -> ScopedJoinHandle<'scope, Result<R>> {
+    scope.spawn(move || {
+        set_keyword_separator(keyword_separator);
+        work()
+    })
+}
+
+/// Waits for the work of a dataset to finish, naming the dataset in its error
+fn join_dataset<R>(handle: ScopedJoinHandle<Result<R>>, request_type: RequestType) -> Result<R> {
+    handle
+        .join()
+        .unwrap_or_else(|_| Err(anyhow!("the thread panicked")))
+        .with_context(|| format!("Could not convert {}", request_type.name()))
+}
+
+/// Reads and parses one dataset of a bundle on its own thread, as for `parse_dataset`
+fn spawn_parse<'scope, T>(
+    scope: &'scope thread::Scope<'scope, '_>,
+    opts: &'scope BundleOpts,
+    request_type: RequestType,
+    keyword_separator: char,
+) -> ScopedJoinHandle<'scope, Result<Vec<T>>>
+where
+    T: DeserializeOwned + Send + 'scope,
+{
+    spawn_dataset(scope, keyword_separator, move || {
+        parse_dataset(&opts.input_dir, request_type, opts.max_rows)
+    })
+}
+
+/// Converts all six datasets to a single JSON document, parsing each on its own thread
+fn bundle_data(opts: &BundleOpts, keyword_separator: char) -> Result<String> {
+    let bundle = thread::scope(|scope| -> Result<OurAirportsData> {
+        let airports = spawn_parse::<Airport>(scope, opts, RequestType::Airport, keyword_separator);
+        let runways = spawn_parse::<Runway>(scope, opts, RequestType::Runway, keyword_separator);
+        let frequencies = spawn_parse::<AirportFrequency>(
+            scope,
+            opts,
+            RequestType::AirportFrequency,
+            keyword_separator,
+        );
+        let navaids = spawn_parse::<Navaid>(scope, opts, RequestType::Navaid, keyword_separator);
+        let countries =
+            spawn_parse::<Country>(scope, opts, RequestType::Country, keyword_separator);
+        let regions = spawn_parse::<Region>(scope, opts, RequestType::Region, keyword_separator);
+        Ok(OurAirportsData::new(
+            join_dataset(airports, RequestType::Airport)?,
+            join_dataset(runways, RequestType::Runway)?,
+            join_dataset(frequencies, RequestType::AirportFrequency)?,
+            join_dataset(navaids, RequestType::Navaid)?,
+            join_dataset(countries, RequestType::Country)?,
+            join_dataset(regions, RequestType::Region)?,
+        ))
+    })?;
+    to_json(&bundle, &opts.output, 2)
+}
+
+/// Reads and parses one dataset, as for `parse_dataset`, and converts it to JSON
+fn dataset_to_json<T>(opts: &BundleOpts, request_type: RequestType) -> Result<String>
+where
+    T: DeserializeOwned + Serialize,
+{
+    let records: Vec<T> = parse_dataset(&opts.input_dir, request_type, opts.max_rows)?;
+    to_json(&records, &opts.output, 1)
+}
+
+/// Writes each dataset to its own JSON file in the output directory, skipping the ones whose file already exists
+/// unless --force. Each dataset is converted on its own thread. A dataset that fails doesn't stop the others,
+/// so that a re-run only retries the failed ones.
+fn bundle_to_dir(opts: &BundleOpts, output_dir: &Path, keyword_separator: char) -> Result<()> {
+    fs::create_dir_all(output_dir)?;
+    let extension = if opts.gzip { "json.gz" } else { "json" };
+    let mut skipped = Vec::new();
+    let mut failed = Vec::new();
+    thread::scope(|scope| {
+        let mut handles = Vec::new();
+        for request_type in RequestType::ALL {
+            let name = request_type.name();
+            let output_path = output_dir.join(format!("{}.{}", name, extension));
+            if output_path.exists() && !opts.force {
+                skipped.push(name);
+                continue;
+            }
+            let handle = spawn_dataset(scope, keyword_separator, move || {
+                let json_out = match request_type {
+                    RequestType::Airport => dataset_to_json::<Airport>(opts, request_type),
+                    RequestType::AirportFrequency => {
+                        dataset_to_json::<AirportFrequency>(opts, request_type)
+                    }
+                    RequestType::Runway => dataset_to_json::<Runway>(opts, request_type),
+                    RequestType::Navaid => dataset_to_json::<Navaid>(opts, request_type),
+                    RequestType::Country => dataset_to_json::<Country>(opts, request_type),
+                    RequestType::Region => dataset_to_json::<Region>(opts, request_type),
+                }?;
+                let data = if opts.gzip {
+                    gzip(json_out.as_bytes())?
+                } else {
+                    json_out.into_bytes()
+                };
+                // written under another name first, so that a failed write isn't skipped as done next time
+                let partial_path = output_dir.join(format!("{}.{}.partial", name, extension));
+                fs::write(&partial_path, data)?;
+                fs::rename(&partial_path, &output_path)?;
+                Ok(())
+            });
+            handles.push((request_type, handle));
+        }
+        // joined in order, so that the failures are always reported in the same order
+        for (request_type, handle) in handles {
+            if let Err(err) = join_dataset(handle, request_type) {
+                warn!("{:#}", err);
+                failed.push(request_type.name());
+            }
+        }
+    });
+
+    if !skipped.is_empty() {
+        info!(
+            "Skipped {} as their files already exist, use --force to write them again",
+            skipped.join(", ")
+        );
+    }
+    if failed.is_empty() {
+        Ok(())
+    } else {
+        Err(anyhow!("Could not write {}", failed.join(", ")))
+    }
+}
+
+/// Applies the safe corrections to every data file, writing the corrected files to the output directory,
+/// and returns the corrections made to each, by file name
+fn fix_data(input_dir: &Option<PathBuf>, output_dir: &Path) -> Result<BTreeMap<String, Vec<Fix>>> {
+    fs::create_dir_all(output_dir)?;
+    let mut fixes = BTreeMap::new();
+    for request_type in &RequestType::ALL {
+        let input_file = input_dir
+            .as_ref()
+            .map(|dir| dataset_file(dir, *request_type));
+        let (data, _) = block_on(read_text(&input_file, *request_type))?;
+        let (fixed, file_fixes) = fix_csv(&data)?;
+        info!(
+            "Fixed {} fields of {}",
+            file_fixes.len(),
+            request_type.filename()
+        );
+        fs::write(output_dir.join(request_type.filename()), fixed)?;
+        fixes.insert(request_type.filename().to_string(), file_fixes);
+    }
+    Ok(fixes)
+}
+
+/// Checks the datasets for issues, failing if any are found.
+/// With --fix, the corrected files are checked instead.
+fn validate_data(opts: &ValidateOpts) -> Result<()> {
+    let fixes = match &opts.fix {
+        Some(output_dir) => Some(fix_data(&opts.input_dir, output_dir)?),
+        None => None,
+    };
+    let input_dir = opts.fix.clone().or_else(|| opts.input_dir.clone());
+    let countries: Vec<Country> = parse_dataset(&input_dir, RequestType::Country, None)?;
+    let airports: Vec<Airport> = parse_dataset(&input_dir, RequestType::Airport, None)?;
+    let navaids: Vec<Navaid> = parse_dataset(&input_dir, RequestType::Navaid, None)?;
+    let regions: Vec<Region> = parse_dataset(&input_dir, RequestType::Region, None)?;
+    let runways: Vec<Runway> = parse_dataset(&input_dir, RequestType::Runway, None)?;
+
+    let report = ValidationReport {
+        unknown_country_codes: unknown_country_codes(
+            &countries,
+            &airports,
+            &navaids,
+            &regions,
+            &opts.allow_country_code,
+        ),
+        unknown_region_codes: unknown_region_codes(&regions, &airports),
+        mismatched_runway_headings: mismatched_runway_headings(&runways, opts.heading_tolerance),
+        out_of_band_frequencies: if opts.check_frequency_band {
+            let frequencies: Vec<AirportFrequency> =
+                parse_dataset(&input_dir, RequestType::AirportFrequency, None)?;
+            Some(out_of_band_frequencies(&frequencies))
+        } else {
+            None
+        },
+        misplaced_airports: if opts.check_country_location {
+            Some(misplaced_airports(&airports))
+        } else {
+            None
+        },
+        swapped_coordinates: if opts.check_swapped_coordinates {
+            Some(swapped_coordinates(&airports, &navaids))
+        } else {
+            None
+        },
+        fixes,
+    };
+    write_output(
+        opts.output.output_file()?,
+        to_json(&report, &opts.output, 2)?,
+    )?;
+
+    if report.is_valid() {
+        Ok(())
+    } else {
+        Err(Error::Validation("issues were found in the data".to_string()).into())
+    }
+}
+
+/// Compresses data with gzip
+fn gzip(data: &[u8]) -> Result<Vec<u8>> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+/// Runs the conversion without producing any output, discarding the records kept and reporting how many there are.
+/// It fails as the conversion would, such as on the first row that fails to parse.
+fn dry_run<T>(
+    opts: &ConvertOpts,
+    request_type: RequestType,
+    transform: impl FnMut(&mut T) -> bool,
+    finish: Finish<T>,
+) -> Result<()>
+where
+    T: DeserializeOwned + Serialize,
+{
+    let (mut input, _) = read_records_input(opts, request_type)?;
+    let mut record_count = 0;
+    for record in stream_records(opts, &mut input, transform, finish)? {
+        record?;
+        record_count += 1;
+    }
+    info!("Parsed {} records", record_count);
+    Ok(())
+}
 
-    let mut country_list: Vec<Country> = Vec::new();
-    for line in rdr.deserialize() {
-        let record: Country = line?;
-        country_list.push(record);
+/// Writes the output to the output file if given, otherwise to stdout
+fn write_output(output_file: Option<&Path>, json_out: String) -> Result<()> {
+    if let Some(output_path) = output_file {
+        fs::write(output_path, json_out)?;
+    } else {
+        // written as bytes ending in \n, so that stdout is the same on every platform
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(json_out.as_bytes())?;
+        stdout.write_all(b"\n")?;
+        stdout.flush()?;
     }
+    Ok(())
+}
 
-    if !pretty_print {
-        let json_out = serde_json::to_string(&country_list)?;
-        Ok(json_out)
+/// Writes binary output to the output file if given, otherwise to stdout
+fn write_binary_output(output_file: Option<&Path>, data: &[u8]) -> Result<()> {
+    if let Some(output_path) = output_file {
+        fs::write(output_path, data)?;
     } else {
-        let json_out = serde_json::to_string_pretty(&country_list)?;
-        Ok(json_out)
+        io::stdout().write_all(data)?;
     }
+    Ok(())
 }
 
-/// Converts region data to JSON
-fn convert_region_data(
-    file_path: &Option<std::path::PathBuf>,
-    pretty_print: bool,
-) -> Result<String> {
-    let data = read_text(&file_path, RequestType::Region)?;
-    eprintln!("Converting data");
-    let mut rdr = csv::Reader::from_reader(data.as_bytes());
+/// Merges duplicate frequencies for --dedupe-frequencies, reporting how many were merged
+fn merge_duplicate_frequencies(
+    frequency_list: Vec<(u64, AirportFrequency)>,
+) -> Vec<(u64, AirportFrequency)> {
+    let (frequency_list, merged_count) =
+        dedupe_frequencies(frequency_list, |(_, frequency)| frequency);
+    info!("Merged {} duplicate frequencies", merged_count);
+    frequency_list
+}
+
+/// Runs a converter of any type with the given options
+fn run_converter<T>(
+    opts: &ConvertOpts,
+    request_type: RequestType,
+    transform: impl FnMut(&mut T) -> bool,
+) -> Result<()>
+where
+    T: DeserializeOwned + Serialize,
+{
+    run_finished_converter(opts, request_type, transform, None)
+}
+
+/// Reads a list of field names from a file, separated by newlines or commas, skipping blank entries
+fn read_field_list(path: &Path) -> Result<Vec<String>> {
+    let text = fs::read_to_string(path).map_err(|err| Error::File(path.to_path_buf(), err))?;
+    Ok(text
+        .split(['\n', ','])
+        .map(str::trim)
+        .filter(|field| !field.is_empty())
+        .map(str::to_string)
+        .collect())
+}
+
+/// Checks that the options of a converter can be used together and with its record type
+fn check_format_options(opts: &ConvertOpts, request_type: RequestType) -> Result<()> {
+    if (opts.geojson
+        || opts.nest_coords
+        || matches!(opts.format, Format::Topojson | Format::Flatgeobuf))
+        && !matches!(request_type, RequestType::Airport | RequestType::Navaid)
+    {
+        return Err(anyhow!(
+            "--geojson, --nest-coords and --format topojson or flatgeobuf are only supported for airports and navaids"
+        ));
+    }
+    if opts.format == Format::Flatgeobuf && opts.nest_coords {
+        return Err(anyhow!(
+            "--nest-coords is not supported with --format flatgeobuf"
+        ));
+    }
+    if opts.format == Format::Topojson
+        && (opts.nest_coords || !opts.fields.is_empty() || !opts.exclude_fields.is_empty())
+    {
+        return Err(anyhow!(
+            "--nest-coords, --fields and --exclude-fields are not supported with --format topojson"
+        ));
+    }
+    if opts.format != Format::Json
+        && (opts.geojson || opts.with_metadata || opts.with_count || opts.group_by.is_some())
+    {
+        return Err(anyhow!(
+            "--geojson, --with-metadata, --with-count and --group-by are only supported with --format json"
+        ));
+    }
+    if opts.append && opts.format != Format::JsonlGz {
+        return Err(anyhow!(
+            "--append is only supported with --format jsonl-gz, as a JSON array or a table can't be added to"
+        ));
+    }
+    if opts.append && opts.split_by.is_some() {
+        return Err(anyhow!("--append is not supported with --split-by"));
+    }
+    if opts.max_output_bytes.is_some() && opts.split_by.is_some() {
+        return Err(anyhow!(
+            "--max-output-bytes is not supported with --split-by"
+        ));
+    }
+    if opts.limit_per_group.is_some() && opts.group_by.is_none() && opts.split_by.is_none() {
+        return Err(anyhow!(
+            "--limit-per-group is only supported with --group-by or --split-by"
+        ));
+    }
+    if matches!(
+        opts.format,
+        Format::JsonlGz | Format::Bson | Format::Flatgeobuf
+    ) && opts.cache_dir.is_some()
+    {
+        return Err(anyhow!(
+            "--cache-dir is not supported with --format jsonl-gz, bson or flatgeobuf"
+        ));
+    }
+    if matches!(opts.format, Format::Bson | Format::Flatgeobuf) && opts.split_by.is_some() {
+        return Err(anyhow!(
+            "--split-by is not supported with --format bson or flatgeobuf"
+        ));
+    }
+    Ok(())
+}
 
-    let mut region_list: Vec<Region> = Vec::new();
-    for line in rdr.deserialize() {
-        let record: Region = line?;
-        region_list.push(record);
+/// Returns the format of an output file from its extension, and whether it's GeoJSON
+fn output_format(output_file: &Path) -> Result<(Format, bool)> {
+    let name = output_file.to_string_lossy();
+    if name.ends_with(".jsonl.gz") {
+        return Ok((Format::JsonlGz, false));
     }
+    let extension = output_file
+        .extension()
+        .and_then(|extension| extension.to_str());
+    Ok(match extension {
+        Some("json") => (Format::Json, false),
+        Some("geojson") => (Format::Json, true),
+        Some("csv") => (Format::Csv, false),
+        Some("tsv") => (Format::Tsv, false),
+        Some("topojson") => (Format::Topojson, false),
+        Some("sql") => (Format::Sql, false),
+        Some("fgb") => (Format::Flatgeobuf, false),
+        _ => {
+            return Err(anyhow!(
+                "can't tell the format of {} from its extension, which should be json, geojson, csv, tsv, topojson, sql, jsonl.gz or fgb",
+                name
+            ))
+        }
+    })
+}
+
+/// Returns the options for writing each of several outputs, in the format of its extension
+fn output_opts(opts: &ConvertOpts) -> Result<Vec<ConvertOpts>> {
+    opts.output
+        .output_files
+        .iter()
+        .map(|output_file| {
+            let (format, geojson) = output_format(output_file)?;
+            Ok(ConvertOpts {
+                output: OutputOpts {
+                    output_files: vec![output_file.clone()],
+                    ..opts.output.clone()
+                },
+                format,
+                geojson,
+                ..opts.clone()
+            })
+        })
+        .collect()
+}
 
-    if !pretty_print {
-        let json_out = serde_json::to_string(&region_list)?;
-        Ok(json_out)
+/// Writes the records kept by `transform` and passed through `finish` to each of several outputs,
+/// parsing the data once
+fn write_outputs<T>(
+    opts: &ConvertOpts,
+    request_type: RequestType,
+    transform: impl FnMut(&mut T) -> bool,
+    finish: Finish<T>,
+) -> Result<()>
+where
+    T: DeserializeOwned + Serialize,
+{
+    let (record_list, source) = load_records(opts, request_type, transform, finish)?;
+    for output_opts in output_opts(opts)? {
+        match output_opts.format {
+            Format::JsonlGz => {
+                let json_lines = format_records(&output_opts, request_type, &source, &record_list)?;
+                let data = gzip(format!("{}\n", json_lines).as_bytes())?;
+                check_output_size(&output_opts, data.len())?;
+                with_jsonl_gz_writer(&output_opts, |writer| {
+                    let mut writer = SizedWriter::new(writer, None);
+                    writer.write_all(&data)?;
+                    writer.finish()
+                })?
+            }
+            Format::Flatgeobuf => write_converted_binary(
+                &output_opts,
+                &flatgeobuf_bytes(&output_opts, request_type, &record_list)?,
+            )?,
+            _ => write_converted_output(
+                &output_opts,
+                format_records(&output_opts, request_type, &source, &record_list)?,
+            )?,
+        }
+    }
+    Ok(())
+}
+
+/// Runs a converter of any type with the given options, passing the records kept by `transform` through `finish`
+fn run_finished_converter<T>(
+    opts: &ConvertOpts,
+    request_type: RequestType,
+    transform: impl FnMut(&mut T) -> bool,
+    finish: Finish<T>,
+) -> Result<()>
+where
+    T: DeserializeOwned + Serialize,
+{
+    if let Some(fields_file) = &opts.fields_file {
+        let opts = ConvertOpts {
+            fields: read_field_list(fields_file)?,
+            fields_file: None,
+            ..opts.clone()
+        };
+        return run_finished_converter(&opts, request_type, transform, finish);
+    }
+    if opts.output.output_files.len() > 1 {
+        if opts.format != Format::Json || opts.geojson {
+            return Err(anyhow!(
+                "--format and --geojson are not supported with more than one --output, as each is written in the format of its extension"
+            ));
+        }
+        if opts.cache_dir.is_some() || opts.split_by.is_some() {
+            return Err(anyhow!(
+                "--cache-dir and --split-by are not supported with more than one --output"
+            ));
+        }
+        for output_opts in output_opts(opts)? {
+            check_format_options(&output_opts, request_type)?;
+        }
     } else {
-        let json_out = serde_json::to_string_pretty(&region_list)?;
-        Ok(json_out)
+        check_format_options(opts, request_type)?;
+    }
+    if opts.since.is_some() && opts.cache_dir.is_some() {
+        return Err(anyhow!("--cache-dir is not supported with --since"));
+    }
+    match &opts.since {
+        Some(since) => {
+            let mut previous = PreviousRecords::read::<T>(since, request_type)?;
+            let mut transform = transform;
+            let mut hash_error = None;
+            let mut changed_transform = |record: &mut T| {
+                // compared before the transform, which may add fields
+                match previous.is_changed(&*record) {
+                    Ok(changed) => transform(record) && changed,
+                    Err(err) => {
+                        hash_error.get_or_insert(err);
+                        false
+                    }
+                }
+            };
+            write_converted(opts, request_type, &mut changed_transform, finish)?;
+            if let Some(err) = hash_error {
+                return Err(err);
+            }
+            let removed_ids = previous.removed_ids();
+            info!(
+                "{} records were removed since {}",
+                removed_ids.len(),
+                since.to_string_lossy()
+            );
+            if let Some(removed_output) = opts.removed_output.as_ref().filter(|_| !opts.dry_run) {
+                fs::write(removed_output, to_json(&removed_ids, &opts.output, 1)?)?;
+            }
+            Ok(())
+        }
+        _ => write_converted(opts, request_type, transform, finish),
+    }
+}
+
+/// Writes the records kept by `transform` and passed through `finish` in the requested format
+fn write_converted<T>(
+    opts: &ConvertOpts,
+    request_type: RequestType,
+    transform: impl FnMut(&mut T) -> bool,
+    finish: Finish<T>,
+) -> Result<()>
+where
+    T: DeserializeOwned + Serialize,
+{
+    if opts.dry_run {
+        dry_run(opts, request_type, transform, finish)
+    } else if opts.output.output_files.len() > 1 {
+        write_outputs(opts, request_type, transform, finish)
+    } else if let (Some(field), Some(output_dir)) = (&opts.split_by, &opts.output_dir) {
+        let (record_list, source) = load_records(opts, request_type, transform, finish)?;
+        write_split(opts, request_type, &source, record_list, field, output_dir)
+    } else if opts.format == Format::JsonlGz {
+        write_jsonl_gz(opts, request_type, transform, finish)
+    } else if opts.format == Format::Bson {
+        write_bson(opts, request_type, transform, finish)
+    } else if opts.format == Format::Flatgeobuf {
+        write_flatgeobuf(opts, request_type, transform, finish)
+    } else {
+        let json_out = convert_data::<T>(opts, request_type, transform, finish)?;
+        write_converted_output(opts, json_out)
     }
 }
 
-fn main() -> Result<()> {
+/// Returns the exit code for an error, based on its class.
+///
+/// | Code | Meaning                                   |
+/// |------|-------------------------------------------|
+/// | 0    | Success                                   |
+/// | 1    | Other error, including invalid arguments  |
+/// | 2    | Network error                             |
+/// | 3    | Input file not found or not readable      |
+/// | 4    | Parse error                               |
+/// | 5    | Validation failure                        |
+fn exit_code(err: &anyhow::Error) -> i32 {
+    let class = err.chain().find_map(|cause| cause.downcast_ref::<Error>());
+    match class {
+        Some(Error::Network(..)) => 2,
+        Some(Error::File(..)) => 3,
+        Some(Error::Parse(_))
+        | Some(Error::Deserialize { .. })
+        | Some(Error::MissingColumn(..)) => 4,
+        Some(Error::Validation(_)) => 5,
+        Some(Error::Io(_)) | Some(Error::Json(_)) | Some(Error::Field(_)) | None => 1,
+    }
+}
+
+fn main() {
     // setup panic handler
     setup_panic!();
+    // parse command args, exiting with 1 on invalid arguments; Opts::parse would exit with 2,
+    // which is used for network errors
+    let opts = Opts::try_parse().unwrap_or_else(|err| err.exit());
+    // log to stderr, at info level (or warn level if quiet, or debug level for this crate if verbose)
+    // unless RUST_LOG is set
+    let default_level = if opts.quiet {
+        "warn"
+    } else if opts.verbose {
+        "info,ourairports=debug"
+    } else {
+        "info"
+    };
+    env_logger::Builder::from_env(Env::default().default_filter_or(default_level)).init();
+
+    if let Err(err) = run(opts.command) {
+        eprintln!("Error: {:?}", err);
+        std::process::exit(exit_code(&err));
+    }
+}
 
-    // match command args
-    match Cli::parse() {
-        // airports
+/// Runs the command given in the args
+fn run(command: Cli) -> Result<()> {
+    match command {
         Cli::Airport {
-            input_file,
-            output_file,
-            pretty_print,
+            opts,
+            keyword_opts,
+            continent_opts,
+            country_opts,
+            bbox_opts,
+            normalize_codes,
+            fill_continent,
+            with_icao_code,
+            only_with_iata,
+            sort_keywords,
+            tristate_scheduled,
+            runway_suitability,
+            heavy_jet_length,
+            regional_jet_length,
+            enrich,
+            countries_file,
+            regions_file,
+            runways_file,
         } => {
-            if let Some(output_path) = output_file {
-                fs::write(
-                    output_path,
-                    convert_airport_data(&input_file, pretty_print)?,
-                )?;
+            set_keyword_separator(keyword_opts.keyword_separator);
+            set_tristate_scheduled(tristate_scheduled);
+            let opts = if fill_continent || enrich || runway_suitability {
+                without_cache(opts)
             } else {
-                println!("{}", convert_airport_data(&input_file, pretty_print)?);
+                opts
+            };
+            let countries: Option<Vec<Country>> = if fill_continent || enrich {
+                Some(parse_input_file(&countries_file, RequestType::Country)?)
+            } else {
+                None
+            };
+            let continents = countries
+                .as_deref()
+                .filter(|_| fill_continent)
+                .map(country_continents);
+            let names = if enrich {
+                let regions: Vec<Region> = parse_input_file(&regions_file, RequestType::Region)?;
+                Some((
+                    country_names(countries.as_deref().unwrap_or_default()),
+                    region_names(&regions),
+                ))
+            } else {
+                None
+            };
+            let runway_lengths = if runway_suitability {
+                let runways: Vec<Runway> = parse_input_file(&runways_file, RequestType::Runway)?;
+                Some(max_runway_lengths(&runways))
+            } else {
+                None
+            };
+            let thresholds = RunwayThresholds {
+                heavy_jet_ft: heavy_jet_length,
+                regional_jet_ft: regional_jet_length,
+            };
+            let mut filled_count = 0;
+            run_converter(&opts, RequestType::Airport, |airport: &mut Airport| {
+                if normalize_codes {
+                    airport.normalize_codes();
+                }
+                if sort_keywords {
+                    ourairports::sort_keywords(&mut airport.keywords);
+                }
+                if with_icao_code {
+                    airport.add_icao_code();
+                }
+                if let Some((country_names, region_names)) = &names {
+                    airport.add_names(country_names, region_names);
+                }
+                if let Some(runway_lengths) = &runway_lengths {
+                    airport.add_runway_suitability(runway_lengths, &thresholds);
+                }
+                if let Some(continents) = &continents {
+                    if airport.fill_continent(continents) {
+                        filled_count += 1;
+                    }
+                }
+                continent_opts.matches(&airport.continent)
+                    && country_opts.matches(&airport.iso_country)
+                    && bbox_opts.matches(airport.latitude_deg, airport.longitude_deg)
+                    && (!only_with_iata || !airport.iata_code.trim().is_empty())
+            })?;
+            if fill_continent {
+                info!("Filled in the continent of {} airports", filled_count);
             }
         }
         Cli::AirportFrequency {
-            input_file,
-            output_file,
-            pretty_print,
+            opts,
+            dedupe_frequencies,
         } => {
-            if let Some(output_path) = output_file {
-                fs::write(
-                    output_path,
-                    convert_airport_frequency_data(&input_file, pretty_print)?,
-                )?;
+            let finish: Finish<AirportFrequency> = if dedupe_frequencies {
+                Some(merge_duplicate_frequencies)
             } else {
-                println!(
-                    "{}",
-                    convert_airport_frequency_data(&input_file, pretty_print)?
-                );
-            }
+                None
+            };
+            run_finished_converter(&opts, RequestType::AirportFrequency, |_| true, finish)?
         }
         Cli::Runway {
-            input_file,
-            output_file,
-            pretty_print,
-        } => {
-            if let Some(output_path) = output_file {
-                fs::write(output_path, convert_runway_data(&input_file, pretty_print)?)?;
-            } else {
-                println!("{}", convert_runway_data(&input_file, pretty_print)?);
+            opts,
+            normalize_headings,
+            with_heading_consistency,
+            heading_tolerance,
+        } => run_converter(&opts, RequestType::Runway, |runway: &mut Runway| {
+            if normalize_headings {
+                runway.normalize_headings();
             }
-        },
+            if with_heading_consistency {
+                runway.add_heading_consistency(heading_tolerance);
+            }
+            true
+        })?,
         Cli::Navaid {
-            input_file,
-            output_file,
-            pretty_print,
+            opts,
+            continent_opts,
+            country_opts,
+            bbox_opts,
+            countries_file,
+            with_band,
         } => {
-            if let Some(output_path) = output_file {
-                fs::write(output_path, convert_navaid_data(&input_file, pretty_print)?)?;
+            let (opts, continents) = if continent_opts.continent.is_empty() {
+                (opts, HashMap::new())
             } else {
-                println!("{}", convert_navaid_data(&input_file, pretty_print)?);
-            }
-        },
+                let countries = parse_input_file(&countries_file, RequestType::Country)?;
+                (without_cache(opts), country_continents(&countries))
+            };
+            run_converter(&opts, RequestType::Navaid, |navaid: &mut Navaid| {
+                if with_band {
+                    navaid.add_band();
+                }
+                continent_opts.matches(
+                    continents
+                        .get(&navaid.iso_country)
+                        .map_or("", String::as_str),
+                ) && country_opts.matches(&navaid.iso_country)
+                    && bbox_opts.matches(navaid.latitude_deg, navaid.longitude_deg)
+            })?
+        }
         Cli::Country {
-            input_file,
-            output_file,
-            pretty_print,
+            opts,
+            keyword_opts,
+            continent_opts,
+            country_opts,
+            sort_keywords,
+            with_regions,
+            full,
+            regions_file,
         } => {
-            if let Some(output_path) = output_file {
-                fs::write(output_path, convert_country_data(&input_file, pretty_print)?)?;
+            set_keyword_separator(keyword_opts.keyword_separator);
+            let (opts, regions) = if with_regions {
+                let region_list: Vec<Region> =
+                    parse_input_file(&regions_file, RequestType::Region)?;
+                (without_cache(opts), regions_by_country(region_list))
             } else {
-                println!("{}", convert_country_data(&input_file, pretty_print)?);
-            }
-        },
+                (opts, HashMap::new())
+            };
+            run_converter(&opts, RequestType::Country, |country: &mut Country| {
+                if sort_keywords {
+                    ourairports::sort_keywords(&mut country.keywords);
+                }
+                if with_regions {
+                    country.add_regions(&regions, full);
+                }
+                continent_opts.matches(&country.continent) && country_opts.matches(&country.code)
+            })?
+        }
         Cli::Region {
-            input_file,
-            output_file,
-            pretty_print,
+            opts,
+            keyword_opts,
+            continent_opts,
+            country_opts,
+            sort_keywords,
+        } => {
+            set_keyword_separator(keyword_opts.keyword_separator);
+            run_converter(&opts, RequestType::Region, |region: &mut Region| {
+                if sort_keywords {
+                    ourairports::sort_keywords(&mut region.keywords);
+                }
+                continent_opts.matches(&region.continent)
+                    && country_opts.matches(&region.iso_country)
+            })?
+        }
+        Cli::Search {
+            query,
+            input,
+            output,
+            keyword_opts,
+            fuzzy,
+            limit,
+        } => {
+            set_keyword_separator(keyword_opts.keyword_separator);
+            let (data, _) = read_input(&input, RequestType::Airport)?;
+            let airport_list = parse_data::<Airport>(&data, None)?;
+            let json_out = if fuzzy {
+                to_json(
+                    &fuzzy_search_airports(&airport_list, &query, limit),
+                    &output,
+                    1,
+                )?
+            } else {
+                to_json(&search_airports(&airport_list, &query), &output, 1)?
+            };
+            write_output(output.output_file()?, json_out)?
+        }
+        Cli::CountryInfo {
+            code,
+            input,
+            airports_file,
+            regions_file,
+            output,
+            keyword_opts,
+        } => {
+            set_keyword_separator(keyword_opts.keyword_separator);
+            let (data, _) = read_input(&input, RequestType::Country)?;
+            let country_list = parse_data::<Country>(&data, None)?;
+            let country = country_list
+                .iter()
+                .find(|country| country.code.eq_ignore_ascii_case(&code))
+                .ok_or_else(|| anyhow!("No country with code {}", code))?;
+            let airport_list =
+                parse_optional_file::<Airport>(&airports_file, RequestType::Airport)?;
+            let region_list = parse_optional_file::<Region>(&regions_file, RequestType::Region)?;
+            let country_info =
+                CountryInfo::new(country, airport_list.as_deref(), region_list.as_deref());
+            write_output(output.output_file()?, to_json(&country_info, &output, 1)?)?
+        }
+        Cli::RegionCounts {
+            input,
+            regions_file,
+            output,
+            keyword_opts,
         } => {
-            if let Some(output_path) = output_file {
-                fs::write(output_path, convert_region_data(&input_file, pretty_print)?)?;
+            set_keyword_separator(keyword_opts.keyword_separator);
+            let (data, _) = read_input(&input, RequestType::Airport)?;
+            let airport_list = parse_data::<Airport>(&data, None)?;
+            let region_list = parse_optional_file::<Region>(&regions_file, RequestType::Region)?;
+            let counts = airports_per_region(&airport_list, region_list.as_deref());
+            write_output(output.output_file()?, to_json(&counts, &output, 1)?)?
+        }
+        Cli::Validate { opts, keyword_opts } => {
+            set_keyword_separator(keyword_opts.keyword_separator);
+            validate_data(&opts)?
+        }
+        Cli::Info { output } => {
+            let mut info_list = serde_json::Map::new();
+            for request_type in &RequestType::ALL {
+                let info = block_on(fetch_info(request_type.url()))?;
+                info_list.insert(request_type.name().to_string(), serde_json::to_value(info)?);
+            }
+            write_output(output.output_file()?, to_json(&info_list, &output, 1)?)?
+        }
+        Cli::Bundle { opts, keyword_opts } => {
+            if let Some(output_dir) = &opts.output_dir {
+                return bundle_to_dir(&opts, output_dir, keyword_opts.keyword_separator);
+            }
+            let json_out = bundle_data(&opts, keyword_opts.keyword_separator)?;
+            if opts.gzip {
+                write_binary_output(opts.output.output_file()?, &gzip(json_out.as_bytes())?)?
             } else {
-                println!("{}", convert_region_data(&input_file, pretty_print)?);
+                write_output(opts.output.output_file()?, json_out)?
             }
-        },
+        }
+        Cli::Codes {
+            input,
+            output,
+            reverse,
+        } => {
+            let (data, _) = read_input(&input, RequestType::Airport)?;
+            let airport_list = parse_data::<Airport>(&data, None)?;
+            let codes = if reverse {
+                icao_to_iata(&airport_list)
+            } else {
+                iata_to_icao(&airport_list)
+            };
+            write_output(output.output_file()?, to_json(&codes, &output, 1)?)?
+        }
+        Cli::AirportDetail {
+            icao,
+            input_dir,
+            radius_nm,
+            output,
+        } => {
+            let data = OurAirportsData::new(
+                parse_dataset(&input_dir, RequestType::Airport, None)?,
+                parse_dataset(&input_dir, RequestType::Runway, None)?,
+                parse_dataset(&input_dir, RequestType::AirportFrequency, None)?,
+                parse_dataset(&input_dir, RequestType::Navaid, None)?,
+                parse_dataset(&input_dir, RequestType::Country, None)?,
+                parse_dataset(&input_dir, RequestType::Region, None)?,
+            );
+            let airport = data
+                .airport_by_icao(&icao)
+                .ok_or_else(|| anyhow!("No airport with ICAO code {}", icao))?;
+            let detail = data.airport_detail(airport, radius_nm);
+            write_output(output.output_file()?, to_json(&detail, &output, 2)?)?
+        }
+        Cli::MergeDatasets {
+            runways_file,
+            airports_file,
+            output_file,
+            keyword_opts,
+        } => {
+            set_keyword_separator(keyword_opts.keyword_separator);
+            let runway_list = parse_input_file::<Runway>(&runways_file, RequestType::Runway)?;
+            let airport_list = parse_input_file::<Airport>(&airports_file, RequestType::Airport)?;
+            let merged = runways_with_airports(&runway_list, &airport_list)?;
+            let table = to_delimited(&merged, b',', None)?;
+            write_output(
+                output_file.as_deref(),
+                table.trim_end_matches('\n').to_string(),
+            )?
+        }
+        Cli::Frequencies {
+            airport,
+            input,
+            output,
+        } => {
+            let (data, _) = read_input(&input, RequestType::AirportFrequency)?;
+            let frequency_list: Vec<AirportFrequency> =
+                parse_data::<AirportFrequency>(&data, None)?
+                    .into_iter()
+                    .filter(|frequency| frequency.airport_ident.eq_ignore_ascii_case(&airport))
+                    .collect();
+            if frequency_list.is_empty() {
+                warn!("No frequencies found for airport {}", airport);
+            }
+            write_output(output.output_file()?, to_json(&frequency_list, &output, 1)?)?
+        }
+        Cli::Tiles {
+            input,
+            zoom,
+            output_dir,
+            output,
+        } => {
+            if !output.output_files.is_empty() {
+                return Err(anyhow!("tiles are written to --output-dir, not --output"));
+            }
+            let (data, _) = read_input(&input, RequestType::Airport)?;
+            let airport_list = parse_data::<Airport>(&data, None)?;
+            let tiles = tile_airports(&airport_list, zoom);
+            for ((x, y), tile) in &tiles {
+                let tile_dir = output_dir.join(zoom.to_string()).join(x.to_string());
+                fs::create_dir_all(&tile_dir)?;
+                fs::write(
+                    tile_dir.join(format!("{}.json", y)),
+                    to_json(tile, &output, 1)?,
+                )?;
+            }
+            info!(
+                "Wrote {} tiles to {}",
+                tiles.len(),
+                output_dir.to_string_lossy()
+            );
+        }
+        Cli::IndexKeywords {
+            dataset,
+            input,
+            output,
+            keyword_opts,
+        } => {
+            set_keyword_separator(keyword_opts.keyword_separator);
+            let index = match dataset {
+                KeywordDataset::Airport => {
+                    let (data, _) = read_input(&input, RequestType::Airport)?;
+                    let airport_list = parse_data::<Airport>(&data, None)?;
+                    keyword_index(
+                        airport_list
+                            .iter()
+                            .map(|airport| (airport.id.as_str(), airport.keywords.as_slice())),
+                    )
+                }
+                KeywordDataset::Country => {
+                    let (data, _) = read_input(&input, RequestType::Country)?;
+                    let country_list = parse_data::<Country>(&data, None)?;
+                    keyword_index(
+                        country_list
+                            .iter()
+                            .map(|country| (country.id.as_str(), country.keywords.as_slice())),
+                    )
+                }
+                KeywordDataset::Region => {
+                    let (data, _) = read_input(&input, RequestType::Region)?;
+                    let region_list = parse_data::<Region>(&data, None)?;
+                    keyword_index(
+                        region_list
+                            .iter()
+                            .map(|region| (region.id.as_str(), region.keywords.as_slice())),
+                    )
+                }
+            };
+            write_output(output.output_file()?, to_json(&index, &output, 1)?)?
+        }
+        Cli::Distinct {
+            dataset,
+            field,
+            input,
+            output,
+            keyword_opts,
+        } => {
+            set_keyword_separator(keyword_opts.keyword_separator);
+            let (data, _) = read_input(&input, dataset.request_type())?;
+            let value_counts = match dataset {
+                Dataset::Airport => distinct_values::<Airport>(&data, &field)?,
+                Dataset::AirportFrequency => distinct_values::<AirportFrequency>(&data, &field)?,
+                Dataset::Runway => distinct_values::<Runway>(&data, &field)?,
+                Dataset::Navaid => distinct_values::<Navaid>(&data, &field)?,
+                Dataset::Country => distinct_values::<Country>(&data, &field)?,
+                Dataset::Region => distinct_values::<Region>(&data, &field)?,
+            };
+            info!("Found {} distinct values of {}", value_counts.len(), field);
+            write_output(output.output_file()?, to_json(&value_counts, &output, 1)?)?
+        }
+        Cli::Repl {
+            dataset,
+            input,
+            output,
+            keyword_opts,
+        } => {
+            set_keyword_separator(keyword_opts.keyword_separator);
+            match dataset {
+                QueryDataset::Airport => {
+                    run_repl::<Airport>(&input, &output, RequestType::Airport)?
+                }
+                QueryDataset::Navaid => run_repl::<Navaid>(&input, &output, RequestType::Navaid)?,
+            }
+        }
     }
 
     Ok(())
 }
+
+/// Loads the records of a dataset once, then writes the JSON answer to each query read from stdin on its own line.
+/// A query that can't be parsed is reported and skipped, so that a typo doesn't end the session
+fn run_repl<T>(input: &InputOpts, output: &OutputOpts, request_type: RequestType) -> Result<()>
+where
+    T: DeserializeOwned + Serialize + HasCountry + HasType + HasCoordinates,
+{
+    let (data, _) = read_input(input, request_type)?;
+    let record_list = parse_data::<T>(&data, None)?;
+    info!("Loaded {} {}", record_list.len(), request_type.name());
+    let prompt = io::stdin().is_terminal();
+    let mut writer = output_writer(output.output_file()?)?;
+    let mut lines = io::stdin().lines();
+    loop {
+        if prompt {
+            eprint!("> ");
+        }
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => break,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        match line.parse::<Query>() {
+            Ok(query) => {
+                writer.write_all(to_json(&query.run(&record_list), output, 1)?.as_bytes())?;
+                writer.write_all(b"\n")?;
+                writer.flush()?;
+            }
+            Err(err) => warn!("{}", err),
+        }
+    }
+    Ok(())
+}
+
+/// Counts the distinct values of a field of csv data of any type
+fn distinct_values<T>(data: &str, field: &str) -> Result<Vec<ValueCount>>
+where
+    T: DeserializeOwned + Serialize,
+{
+    let records = parse_records::<T>(data, None, false)?
+        .map(|record| Ok(serde_json::to_value(record?.1)?))
+        .collect::<Result<Vec<serde_json::Value>>>()?;
+    Ok(count_distinct(records, field)?)
+}
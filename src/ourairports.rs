@@ -1,57 +1,130 @@
-use serde::de::{self, Unexpected};
+use crate::{AircraftCategory, AirportType, CountryRegions, NavaidBand, NavaidType};
+use serde::de::{self, DeserializeOwned, Unexpected};
 use serde::{Deserialize, Deserializer, Serialize};
+use std::cell::Cell;
+
+thread_local! {
+    /// The separator between keywords in the `keywords` field
+    static KEYWORD_SEPARATOR: Cell<char> = const { Cell::new(',') };
+    /// Whether records are being deserialized from their JSON form rather than from CSV
+    static FROM_JSON: Cell<bool> = const { Cell::new(false) };
+    /// Whether a blank `scheduled_service` is parsed as unknown rather than being an error
+    static TRISTATE_SCHEDULED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Sets the separator between keywords in the `keywords` field for records parsed on the current thread.
+/// Defaults to a comma.
+pub fn set_keyword_separator(separator: char) {
+    KEYWORD_SEPARATOR.with(|cell| cell.set(separator));
+}
+
+/// Sets whether a blank `scheduled_service` of airports parsed on the current thread is unknown,
+/// rather than an error, so that it's `None`. Defaults to false.
+pub fn set_tristate_scheduled(tristate: bool) {
+    TRISTATE_SCHEDULED.with(|cell| cell.set(tristate));
+}
 
 /// Contains a record of a single airport.
 #[derive(Deserialize, Serialize)]
 pub struct Airport {
     /// Internal OurAirports integer identifier for the airport.
     /// This will stay persistent, even if the airport code changes.
-    id: String,
+    pub id: String,
     /// The text identifier used in the OurAirports URL.
     /// This will be the ICAO code if available. Otherwise, it will be a local airport code (if no conflict), or if nothing else is available, an internally-generated code starting with the ISO2 country code, followed by a dash and a four-digit number.
-    ident: String,
+    pub ident: String,
     /// The type of the airport.
-    /// Allowed values are "closed_airport", "heliport", "large_airport", "medium_airport", "seaplane_base", and "small_airport".
+    /// Allowed values are "balloonport", "closed" (named "closed_airport" in the data dictionary), "heliport", "large_airport", "medium_airport", "seaplane_base", and "small_airport".
     #[serde(rename = "type")]
-    airport_type: String,
+    pub airport_type: AirportType,
     /// The official airport name, including "Airport", "Airstrip", etc.
-    name: String,
-    /// The airport latitude in decimal degrees (positive for north).
-    latitude_deg: f64,
-    /// The airport longitude in decimal degrees (positive for east).
-    longitude_deg: f64,
+    pub name: String,
+    /// The airport latitude in decimal degrees (positive for north), if available.
+    pub latitude_deg: Option<f64>,
+    /// The airport longitude in decimal degrees (positive for east), if available.
+    pub longitude_deg: Option<f64>,
     /// The airport elevation MSL in feet (*not* metres). None if unavailable.
-    elevation_ft: Option<i32>,
+    pub elevation_ft: Option<i32>,
     /// The code for the continent where the airport is (primarily) located.
     /// Allowed values are "AF" (Africa), "AN" (Antarctica), "AS" (Asia), "EU" (Europe), "NA" (North America), "OC" (Oceania), or "SA" (South America).
-    continent: String,
+    pub continent: String,
     /// The two-character ISO 3166:1-alpha2 code for the country where the airport is (primarily) located.
     /// A handful of unofficial, non-ISO codes are also in use, such as "XK" for Kosovo.
-    iso_country: String,
+    pub iso_country: String,
+    /// The name of the country of the airport, as added by `Airport::add_names`. Not part of the OurAirports data,
+    /// so only present when added, and null if the country is unknown.
+    #[serde(
+        default,
+        deserialize_with = "present_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub country_name: Option<Option<String>>,
     /// An alphanumeric code for the high-level administrative subdivision of a country where the airport is primarily located (e.g. province, governorate), prefixed by the ISO2 country code and a hyphen.
     /// OurAirports uses ISO 3166:2 codes whenever possible, preferring higher administrative levels, but also includes some custom codes.
-    iso_region: String,
+    pub iso_region: String,
+    /// The name of the region of the airport, as added by `Airport::add_names`. Not part of the OurAirports data,
+    /// so only present when added, and null if the region is unknown.
+    #[serde(
+        default,
+        deserialize_with = "present_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub region_name: Option<Option<String>>,
     /// The primary municipality that the airport serves (when available).
     /// Note that this is *not* necessarily the municipality where the airport is physically located.
-    municipality: String,
+    pub municipality: String,
     /// true if the airport currently has scheduled airline service; false otherwise.
-    #[serde(deserialize_with = "bool_from_str")]
-    scheduled_service: bool,
+    /// Only `None`, written as null, when it was blank and parsed with `set_tristate_scheduled`.
+    #[serde(deserialize_with = "scheduled_from_str")]
+    pub scheduled_service: Option<bool>,
     /// The code that an aviation GPS database (such as Jeppesen's or Garmin's) would normally use for the airport. This will always be the ICAO code if one exists.
     /// Note that, unlike the `ident` column, this is *not* guaranteed to be globally unique.
-    gps_code: String,
+    pub gps_code: String,
+    /// The ICAO code of the airport, as added by `Airport::add_icao_code`. Not part of the OurAirports data,
+    /// so only present when added.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icao_code: Option<String>,
     /// The three-letter IATA code for the airport (if it has one).
-    iata_code: String,
+    pub iata_code: String,
     /// The local country code for the airport, if different from the `gps_code` and `iata_code` fields (used mainly for US airports).
-    local_code: String,
+    pub local_code: String,
     /// URL of the airport's official home page on the web, if one exists.
-    home_link: String,
+    pub home_link: String,
     /// URL of the airport's page on Wikipedia, if one exists.
-    wikipedia_link: String,
+    pub wikipedia_link: String,
     /// Extra keywords/phrases to assist with search, as a Vec.
     /// May include former names for the airport, alternate codes, names in other languages, nearby tourist destinations, etc.
     #[serde(deserialize_with = "vec_string_from_string")]
-    keywords: Vec<String>,
+    pub keywords: Vec<String>,
+    /// The length of the longest open runway of the airport in feet, as added by `Airport::add_runway_suitability`.
+    /// Not part of the OurAirports data, so only present when added, and null if the airport has no open runway
+    /// with a known length.
+    #[serde(
+        default,
+        deserialize_with = "present_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub max_runway_length_ft: Option<Option<u32>>,
+    /// The largest category of aircraft the longest open runway is suitable for, as added by
+    /// `Airport::add_runway_suitability`. Not part of the OurAirports data, so only present when added,
+    /// and null if the airport has no open runway with a known length.
+    #[serde(
+        default,
+        deserialize_with = "present_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub suitable_for: Option<Option<AircraftCategory>>,
+}
+
+impl Airport {
+    /// Uppercases the code fields of the airport: `ident`, `gps_code`, `iata_code` and `local_code`.
+    /// Other fields, such as `name` and `municipality`, are left untouched.
+    pub fn normalize_codes(&mut self) {
+        self.ident.make_ascii_uppercase();
+        self.gps_code.make_ascii_uppercase();
+        self.iata_code.make_ascii_uppercase();
+        self.local_code.make_ascii_uppercase();
+    }
 }
 
 /// Contains information about a single airport radio frequency
@@ -60,22 +133,22 @@ pub struct Airport {
 pub struct AirportFrequency {
     /// Internal OurAirports integer identifier for the frequency.
     /// This will stay persistent, even if the radio frequency or description changes.
-    id: String,
+    pub id: String,
     /// Internal integer foreign key matching the `id` column for the associated airport in Airports struct.
     /// (`airport_ident` is a better alternative.)
-    airport_ref: String,
+    pub airport_ref: String,
     /// Externally-visible string foreign key matching the `ident` column for the associated airport in Airports.
-    airport_ident: String,
+    pub airport_ident: String,
     /// A code for the frequency type.
     /// This isn't (currently) a controlled vocabulary, but probably will be soon.
     /// Some common values are "TWR" (tower), "ATF" or "CTAF" (common traffic frequency), "GND" (ground control), "RMP" (ramp control), "ATIS" (automated weather), "RCO" (remote radio outlet), "ARR" (arrivals), "DEP" (departures), "UNICOM" (monitored ground station), and "RDO" (a flight-service station).
     #[serde(rename = "type")]
-    frequency_type: String,
+    pub frequency_type: String,
     /// A description of the frequency, typically the way a pilot would open a call on it.
-    description: String,
+    pub description: String,
     /// Radio voice frequency in megahertz.
     /// Note that the same frequency may appear multiple times for an airport, serving different functions.
-    frequency_mhz: String,
+    pub frequency_mhz: String,
 }
 
 /// Contains information about a single landing surface
@@ -83,51 +156,84 @@ pub struct AirportFrequency {
 pub struct Runway {
     /// Internal OurAirports integer identifier for the runway.
     /// This will stay persistent, even if the runway numbering changes.
-    id: String,
+    pub id: String,
     /// Internal integer foreign key matching the id column for the associated airport in airports.csv. (`airport_ident` is a better alternative.)
-    airport_ref: String,
+    pub airport_ref: String,
     /// Externally-visible string foreign key matching the ident column for the associated airport in airports.csv.
-    airport_ident: String,
+    pub airport_ident: String,
     /// Length of the full runway surface (including displaced thresholds, overrun areas, etc) in feet.
-    length_ft: Option<u32>,
+    pub length_ft: Option<u32>,
     /// Width of the runway surface in feet.
-    width_ft: Option<u32>,
+    pub width_ft: Option<u32>,
     /// Code for the runway surface type.
     /// This is not yet a controlled vocabulary, but probably will be soon.
     /// Some common values include "ASP" (asphalt), "TURF" (turf), "CON" (concrete), "GRS" (grass), "GRE" (gravel), "WATER" (water), and "UNK" (unknown).
-    surface: String,
+    pub surface: String,
     /// `true` if the surface is lighted at night. `false` otherwise.
     #[serde(deserialize_with = "bool_from_str")]
-    lighted: bool,
+    pub lighted: bool,
     /// `true` if the runway surface is currently closed, `false` otherwise.
     #[serde(deserialize_with = "bool_from_str")]
-    closed: bool,
+    pub closed: bool,
     /// Identifier for the low-numbered end of the runway.
-    le_ident: String,
+    pub le_ident: String,
     /// Latitude of the centre of the low-numbered end of the runway, in decimal degrees (positive is north), if available.
-    le_latitude_deg: Option<f64>,
+    pub le_latitude_deg: Option<f64>,
     /// Longitude of the centre of the low-numbered end of the runway, in decimal degrees (positive is east), if available.
-    le_longitude_deg: Option<f64>,
+    pub le_longitude_deg: Option<f64>,
     /// Elevation above MSL of the low-numbered end of the runway in feet.
-    le_elevation_ft: Option<i32>,
+    pub le_elevation_ft: Option<i32>,
     /// Heading of the low-numbered end of the runway in degrees true (*not* magnetic).
     #[serde(rename = "le_heading_degT")]
-    le_heading_deg_true: Option<f64>,
+    pub le_heading_deg_true: Option<f64>,
     /// Length of the displaced threshold (if any) for the low-numbered end of the runway, in feet.
-    le_displaced_threshold_ft: Option<i32>,
+    pub le_displaced_threshold_ft: Option<i32>,
     /// Identifier for the high-numbered end of the runway.
-    he_ident: String,
+    pub he_ident: String,
     /// Latitude of the centre of the high-numbered end of the runway, in decimal degrees (positive is north), if available.
-    he_latitude_deg: Option<f64>,
+    pub he_latitude_deg: Option<f64>,
     /// Longitude of the centre of the high-numbered end of the runway, in decimal degrees (positive is east), if available.
-    he_longitude_deg: Option<f64>,
+    pub he_longitude_deg: Option<f64>,
     /// Elevation above MSL of the high-numbered end of the runway in feet.
-    he_elevation_ft: Option<i32>,
+    pub he_elevation_ft: Option<i32>,
     #[serde(rename = "he_heading_degT")]
     /// Heading of the high-numbered end of the runway in degrees true (*not* magnetic).
-    he_heading_deg_true: Option<f64>,
+    pub he_heading_deg_true: Option<f64>,
     /// Length of the displaced threshold (if any) for the high-numbered end of the runway, in feet.
-    he_displaced_threshold_ft: Option<i32>,
+    pub he_displaced_threshold_ft: Option<i32>,
+    /// Whether the two ends of the runway are roughly opposite each other, as added by `Runway::add_heading_consistency`.
+    /// Not part of the OurAirports data, so only present when added, and null if either heading is missing.
+    #[serde(
+        default,
+        deserialize_with = "present_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub heading_consistency: Option<Option<bool>>,
+}
+
+impl Runway {
+    /// Maps `le_heading_deg_true` and `he_heading_deg_true` into the range [0, 360),
+    /// e.g. -10 becomes 350. Missing headings are left as None.
+    pub fn normalize_headings(&mut self) {
+        if let Some(heading) = &mut self.le_heading_deg_true {
+            *heading = heading.rem_euclid(360.0);
+        }
+        if let Some(heading) = &mut self.he_heading_deg_true {
+            *heading = heading.rem_euclid(360.0);
+        }
+    }
+
+    /// Returns whether `le_heading_deg_true` and `he_heading_deg_true` are within `tolerance` degrees
+    /// of being 180 degrees apart, or None if either heading is missing
+    pub fn headings_are_reciprocal(&self, tolerance: f64) -> Option<bool> {
+        let (le, he) = (self.le_heading_deg_true?, self.he_heading_deg_true?);
+        Some(((he - le).rem_euclid(360.0) - 180.0).abs() <= tolerance)
+    }
+
+    /// Adds the `heading_consistency` field, from `headings_are_reciprocal`
+    pub fn add_heading_consistency(&mut self, tolerance: f64) {
+        self.heading_consistency = Some(self.headings_are_reciprocal(tolerance));
+    }
 }
 
 /// Represents a single radio navigation
@@ -135,56 +241,72 @@ pub struct Runway {
 pub struct Navaid {
     /// Internal OurAirports integer identifier for the navaid.
     /// This will stay persistent, even if the navaid identifier or frequency changes.
-    id: String,
+    pub id: String,
     /// This is a unique string identifier constructed from the navaid name and country, and used in the OurAirports URL.
-    filename: String,
+    pub filename: String,
     /// The 1-3 character identifer that the navaid transmits.
-    ident: String,
+    pub ident: String,
     /// The name of the navaid, excluding its type.
-    name: String,
+    pub name: String,
     /// The type of the navaid. Options are "DME", "NDB", "NDB-DME", "TACAN", "VOR", "VOR-DME", or "VORTAC".
     /// See the [map legend](https://ourairports.com/help/data-dictionary.html#navaids) for more information about each type.
     #[serde(rename = "type")]
-    navaid_type: String,
+    pub navaid_type: NavaidType,
     /// The frequency of the navaid in *kilohertz*.
     /// If the Navaid operates on the VHF band (VOR, VOR-DME) or operates on the UHF band with a paired VHF frequency (DME, TACAN, VORTAC), then you need to divide this number by 1,000 to get the frequency in megahertz (115.3 MHz in this example).
     /// For an NDB or NDB-DME, you can use this frequency directly.
-    frequency_khz: String,
+    pub frequency_khz: String,
     /// The latitude of the navaid in decimal degrees (negative for south).
-    latitude_deg: Option<f64>,
+    pub latitude_deg: Option<f64>,
     /// The longitude of the navaid in decimal degrees (negative for west).
-    longitude_deg: Option<f64>,
+    pub longitude_deg: Option<f64>,
     /// The navaid's elevation MSL in feet (not metres).
-    elevation_ft: Option<i32>,
+    pub elevation_ft: Option<i32>,
     /// The two-character [ISO 3166:1-alpha2 code](https://en.wikipedia.org/wiki/List_of_ISO_3166_country_codes) for the country that operates the navaid.
     /// A handful of unofficial, non-ISO codes are also in use, such as "XK" for [Kosovo](https://ourairports.com/countries/XK/).
-    iso_country: String,
+    pub iso_country: String,
     /// The paired VHF frequency for the DME (or TACAN) in kilohertz.
     /// Divide by 1,000 to get the paired VHF frequency in megahertz (e.g. 115.3 MHz).
-    dme_frequency_khz: String,
+    pub dme_frequency_khz: String,
     /// The DME channel (an alternative way of tuning distance-measuring equipment)
-    dme_channel: String,
+    pub dme_channel: String,
     /// The latitude of the associated DME in decimal degrees (negative for south). If missing, assume that the value is the same as `latitude_deg`.
-    dme_latitude_deg: Option<f64>,
+    pub dme_latitude_deg: Option<f64>,
     /// The longitude of the associated DME in decimal degrees (negative for west). If missing, assume that the value is the same as `longitude_deg`.
-    dme_longitude_deg: Option<f64>,
+    pub dme_longitude_deg: Option<f64>,
     /// The associated DME transmitters elevation MSL in feet. If missing, assume that it's the same value as `elevation_ft`.
-    dme_elevation_ft: Option<i32>,
+    pub dme_elevation_ft: Option<i32>,
     /// The magnetic variation adjustment built into a VOR's, VOR-DME's, or TACAN's radials. Positive means east (added to the true direction), and negative means west (subtracted from the true direction).
     /// This will not usually be the same as `magnetic_variation_deg` because the magnetic pole is constantly in motion.
-    slaved_variation_deg: Option<f64>,
+    pub slaved_variation_deg: Option<f64>,
     /// The actual magnetic variation at the navaid's location. Positive means east (added to the true direction), and negative means west (subtracted from the true direction),
-    magnetic_variation_deg: Option<f64>,
+    pub magnetic_variation_deg: Option<f64>,
     /// The primary function of the navaid in the airspace system.
     /// Options include "HI" (high-altitude airways, at or above flight level 180), "LO" (low-altitude airways), "BOTH" (high- and low-altitude airways), "TERM" (terminal-area navigation only), and "RNAV" (non-GPS area navigation).
     #[serde(rename = "usageType")]
-    usage_type: String,
+    pub usage_type: String,
     /// The power-output level of the navaid.
     /// Options include "HIGH", "MEDIUM", "LOW", and "UNKNOWN".
-    power: String,
+    pub power: String,
     /// The OurAirports text identifier (usually the ICAO code) for an airport associated with the navaid.
     /// Links to the `ident` column in airports.csv.
-    associated_airport: String,
+    pub associated_airport: String,
+    /// The service range of the navaid in nautical miles, from the `range_nm` column of newer dumps of the data.
+    /// Missing if the data has no such column, and null if it's blank.
+    #[serde(
+        default,
+        deserialize_with = "present_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub range_nm: Option<Option<u32>>,
+    /// The radio band of the navaid, as added by `Navaid::add_band`.
+    /// Not part of the OurAirports data, so only present when added, and null if the band isn't known.
+    #[serde(
+        default,
+        deserialize_with = "present_option",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub band: Option<Option<NavaidBand>>,
 }
 
 /// Represents a country or country-like entity (e.g. Hong Kong)
@@ -192,49 +314,95 @@ pub struct Navaid {
 pub struct Country {
     /// Internal OurAirports integer identifier for the country.
     /// This will stay persistent, even if the country name or code changes.
-    id: String,
+    pub id: String,
     /// The two-character [ISO 3166:1-alpha2 code](https://en.wikipedia.org/wiki/List_of_ISO_3166_country_codes) for the country that operates the navaid.
     /// A handful of unofficial, non-ISO codes are also in use, such as "XK" for [Kosovo](https://ourairports.com/countries/XK/).
     /// The `iso_country` field in airports.csv points into this field.
-    code: String,
+    pub code: String,
     /// The common **English**-language name for the country.
     /// Other variations of the name may appear in the `keywords` field to assist with search.
-    name: String,
+    pub name: String,
     /// The code for the continent where the country is (primarily) located.
     /// See the `continent` code in airports.csv for allowed values.
-    continent: String,
+    pub continent: String,
     /// Link to the Wikipedia article about the country.
-    wikipedia_link: String,
+    pub wikipedia_link: String,
     /// An array of search keywords/phrases related to the country.
     #[serde(deserialize_with = "vec_string_from_string")]
-    keywords: Vec<String>,
+    pub keywords: Vec<String>,
+    /// The regions of the country, as added by `Country::add_regions`.
+    /// Not part of the OurAirports data, so only present when added.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub regions: Option<CountryRegions>,
 }
 
 /// Represents a high-level administrative subdivision of a country
-#[derive(Deserialize, Serialize)]
+#[derive(Clone, Deserialize, Serialize)]
 pub struct Region {
     /// Internal OurAirports integer identifier for the region. This will stay persistent, even if the region code changes.
-    id: String,
+    pub id: String,
     /// `local_code` prefixed with the country code to make a globally-unique identifier.
-    code: String,
+    pub code: String,
     /// The local code for the administrative subdivision.
     /// Whenever possible, these are official [ISO 3166:2](https://en.wikipedia.org/wiki/ISO_3166-2), at the highest level available, but in some cases OurAirports has to use unofficial codes.
     /// There is also a pseudo code "U-A" for each country, which means that the airport has not yet been assigned to a region (or perhaps can't be, as in the case of a deep-sea oil platform).
-    local_code: String,
+    pub local_code: String,
     /// The common **English**-language name for the administrative subdivision.
     /// In some cases, the name in local languages will appear in the `keywords` field assist search.
-    name: String,
+    pub name: String,
     /// A code for the continent to which the region belongs.
     /// See the `continent` field in airports.csv for a list of codes.
-    continent: String,
+    pub continent: String,
     /// The two-character [ISO 3166:1-alpha2 code](https://en.wikipedia.org/wiki/List_of_ISO_3166_country_codes) for the country containing the administrative subdivision.
     /// A handful of unofficial, non-ISO codes are also in use, such as "XK" for [Kosovo](https://ourairports.com/countries/XK/).
-    iso_country: String,
+    pub iso_country: String,
     /// A link to the Wikipedia article describing the subdivision.
-    wikipedia_link: String,
+    pub wikipedia_link: String,
     /// An array of keywords to assist with search. May include former names for the region, and/or the region name in other languages.
     #[serde(deserialize_with = "vec_string_from_string")]
-    keywords: Vec<String>,
+    pub keywords: Vec<String>,
+}
+
+impl Region {
+    /// Returns whether this is the pseudo region "U-A" of a country,
+    /// for airports that have not been assigned to a region.
+    pub fn is_unassigned(&self) -> bool {
+        is_unassigned_region(&self.code)
+    }
+}
+
+/// Returns whether a region code, such as the `iso_region` of an airport, is the pseudo region "U-A" of a country
+/// (e.g. "US-U-A"), for airports that have not been assigned to a region.
+pub fn is_unassigned_region(code: &str) -> bool {
+    code == "U-A" || code.ends_with("-U-A")
+}
+
+/// Sorts keywords alphabetically ignoring case, and keywords differing only in case by their exact text,
+/// so that the same keywords are in the same order whichever order they were listed in.
+pub fn sort_keywords(keywords: &mut [String]) {
+    keywords.sort_by(|a, b| {
+        a.to_lowercase()
+            .cmp(&b.to_lowercase())
+            .then_with(|| a.cmp(b))
+    });
+}
+
+/// Deserializes a record from its JSON form, as produced by serializing it.
+pub fn from_json_value<T: DeserializeOwned>(value: serde_json::Value) -> crate::error::Result<T> {
+    FROM_JSON.with(|cell| cell.set(true));
+    let record = serde_json::from_value(value);
+    FROM_JSON.with(|cell| cell.set(false));
+    Ok(record?)
+}
+
+/// Deserializes a field that is only present when added, or when its column is in the data, as `Some`,
+/// so that a null or a blank value is read as present but unknown. A missing field is None, with `#[serde(default)]`.
+fn present_option<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::deserialize(deserializer).map(Some)
 }
 
 /// Converts a string to a boolean based on "yes" and "no"
@@ -242,8 +410,11 @@ fn bool_from_str<'de, D>(deserializer: D) -> Result<bool, D::Error>
 where
     D: Deserializer<'de>,
 {
+    if FROM_JSON.with(Cell::get) {
+        return bool::deserialize(deserializer);
+    }
     match String::deserialize(deserializer)?.to_lowercase().as_str() {
-        "yes" | "1"=> Ok(true),
+        "yes" | "1" => Ok(true),
         "no" | "0" => Ok(false),
         other => Err(de::Error::invalid_value(
             Unexpected::Str(other),
@@ -252,14 +423,39 @@ where
     }
 }
 
-/// Transforms a comma-separated string to a vector.
+/// Transforms "yes" and "no" to a boolean as `bool_from_str`, and a blank value (or null in JSON) to `None`
+/// if set with `set_tristate_scheduled`
+fn scheduled_from_str<'de, D>(deserializer: D) -> Result<Option<bool>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    if FROM_JSON.with(Cell::get) {
+        return Option::deserialize(deserializer);
+    }
+    let value = String::deserialize(deserializer)?;
+    if value.is_empty() && TRISTATE_SCHEDULED.with(Cell::get) {
+        return Ok(None);
+    }
+    bool_from_str(de::value::StrDeserializer::new(&value)).map(Some)
+}
+
+/// Transforms a string separated by the keyword separator (a comma by default) to a vector.
 fn vec_string_from_string<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
 where
     D: Deserializer<'de>,
 {
+    if FROM_JSON.with(Cell::get) {
+        return Vec::deserialize(deserializer);
+    }
     let keywords = String::deserialize(deserializer)?;
     match keywords.len() {
         0 => Ok(vec![]),
-        _ => Ok(keywords.split(',').map(|s| s.trim().to_string()).collect()),
+        _ => {
+            let separator = KEYWORD_SEPARATOR.with(Cell::get);
+            Ok(keywords
+                .split(separator)
+                .map(|s| s.trim().to_string())
+                .collect())
+        }
     }
 }
@@ -0,0 +1,88 @@
+use crate::error::Result;
+use crate::{Error, RequestType};
+use serde::de::DeserializeOwned;
+use std::fs::File;
+use std::io::Read;
+use std::iter;
+use std::path::Path;
+
+/// Parses every record of csv data of any type, already in memory
+pub fn parse_from_bytes<T>(bytes: &[u8]) -> Result<Vec<T>>
+where
+    T: DeserializeOwned,
+{
+    parse_from_reader(bytes).collect()
+}
+
+/// Parses the records of csv data of any type as they are read, without reading all of the data into memory.
+///
+/// The data is not decoded upfront, so unlike `read_text`, invalid UTF-8 in a field is a parse error
+/// of that record instead of being replaced. Blank rows, such as lines of whitespace or commas at the end
+/// of a file, are skipped.
+pub fn parse_from_reader<T, R>(reader: R) -> impl Iterator<Item = Result<T>>
+where
+    T: DeserializeOwned,
+    R: Read,
+{
+    let mut rdr = csv::Reader::from_reader(reader);
+    // as for csv::Reader::deserialize, records are read by position if the header row can't be read
+    let headers = rdr.headers().ok().cloned();
+    non_blank_rows(rdr).map(move |row| Ok(row?.deserialize(headers.as_ref())?))
+}
+
+/// Returns whether every field of a row of csv data is empty or whitespace
+fn is_blank_row(row: &csv::StringRecord) -> bool {
+    row.iter().all(|field| field.trim().is_empty())
+}
+
+/// Reads the rows of csv data that aren't blank, as `parse_from_reader` does before parsing them into records,
+/// for readers configured differently, such as to trim fields.
+/// A blank row with a different number of fields than the header, such as a line of whitespace, is skipped
+/// rather than failing to parse.
+pub fn non_blank_rows<R: Read>(
+    mut rdr: csv::Reader<R>,
+) -> impl Iterator<Item = Result<csv::StringRecord>> {
+    let mut row = csv::StringRecord::new();
+    iter::from_fn(move || loop {
+        match rdr.read_record(&mut row) {
+            Ok(false) => return None,
+            Ok(true) if is_blank_row(&row) => continue,
+            Ok(true) => return Some(Ok(row.clone())),
+            // the fields of a row with the wrong number of them are still read into it
+            Err(err)
+                if matches!(err.kind(), csv::ErrorKind::UnequalLengths { .. })
+                    && is_blank_row(&row) =>
+            {
+                continue
+            }
+            Err(err) => return Some(Err(Error::from(err))),
+        }
+    })
+}
+
+/// Parses the records of a local csv file of any type as they are read, like `parse_from_reader`
+pub fn parse_file<T>(path: &Path) -> Result<impl Iterator<Item = Result<T>>>
+where
+    T: DeserializeOwned,
+{
+    let file = File::open(path).map_err(|err| Error::File(path.to_path_buf(), err))?;
+    Ok(parse_from_reader(file))
+}
+
+/// Checks that the header row of csv data has every column of the data type, in any order,
+/// so that a renamed or removed column is reported before parsing any record
+pub fn check_columns(bytes: &[u8], request_type: RequestType) -> Result<()> {
+    let mut rdr = csv::Reader::from_reader(bytes);
+    let headers = rdr.headers().map_err(Error::Parse)?;
+    match request_type
+        .columns()
+        .iter()
+        .find(|column| !headers.iter().any(|header| header == **column))
+    {
+        Some(column) => Err(Error::MissingColumn(
+            column.to_string(),
+            headers.iter().map(str::to_string).collect(),
+        )),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,148 @@
+use crate::error::Result;
+use crate::{parse_from_reader, Airport, AirportType, Navaid, NavaidType, Region};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::io::{Read, Write};
+use std::str::FromStr;
+
+/// Records with the code of a country
+pub trait HasCountry {
+    /// Returns the `iso_country` of the record
+    fn iso_country(&self) -> &str;
+}
+
+impl HasCountry for Airport {
+    fn iso_country(&self) -> &str {
+        &self.iso_country
+    }
+}
+
+impl HasCountry for Navaid {
+    fn iso_country(&self) -> &str {
+        &self.iso_country
+    }
+}
+
+impl HasCountry for Region {
+    fn iso_country(&self) -> &str {
+        &self.iso_country
+    }
+}
+
+/// Records with a type
+pub trait HasType {
+    /// The type of the records, e.g. `AirportType`
+    type Type: Copy + PartialEq + FromStr;
+
+    /// Returns the `type` of the record, e.g. `AirportType::LargeAirport` or `NavaidType::Vor`
+    fn record_type(&self) -> Self::Type;
+}
+
+impl HasType for Airport {
+    type Type = AirportType;
+
+    fn record_type(&self) -> AirportType {
+        self.airport_type
+    }
+}
+
+impl HasType for Navaid {
+    type Type = NavaidType;
+
+    fn record_type(&self) -> NavaidType {
+        self.navaid_type
+    }
+}
+
+/// Records located at a point
+pub trait HasCoordinates {
+    /// Returns the `latitude_deg` and `longitude_deg` of the record, if it has both
+    fn coordinates(&self) -> Option<(f64, f64)>;
+}
+
+impl HasCoordinates for Airport {
+    fn coordinates(&self) -> Option<(f64, f64)> {
+        Some((self.latitude_deg?, self.longitude_deg?))
+    }
+}
+
+impl HasCoordinates for Navaid {
+    fn coordinates(&self) -> Option<(f64, f64)> {
+        Some((self.latitude_deg?, self.longitude_deg?))
+    }
+}
+
+/// Filters records of any type one at a time as they are parsed, without collecting them,
+/// e.g. `Pipeline::<Airport>::new(file).filter_country("US").filter_type(AirportType::LargeAirport).write_json(writer)`
+pub struct Pipeline<'a, T> {
+    records: Box<dyn Iterator<Item = Result<T>> + 'a>,
+}
+
+impl<'a, T> Pipeline<'a, T>
+where
+    T: DeserializeOwned + 'a,
+{
+    /// Creates a pipeline of the records of csv data, parsed as they are read like `parse_from_reader`
+    pub fn new<R: Read + 'a>(reader: R) -> Self {
+        Pipeline {
+            records: Box::new(parse_from_reader(reader)),
+        }
+    }
+}
+
+impl<'a, T: 'a> Pipeline<'a, T> {
+    /// Only keeps the records for which the predicate returns true. Parse errors are always kept.
+    pub fn filter(self, mut predicate: impl FnMut(&T) -> bool + 'a) -> Self {
+        Pipeline {
+            records: Box::new(
+                self.records
+                    .filter(move |record| record.as_ref().map_or(true, &mut predicate)),
+            ),
+        }
+    }
+
+    /// Only keeps the records in the country with this code, ignoring case
+    pub fn filter_country(self, code: &str) -> Self
+    where
+        T: HasCountry,
+    {
+        let code = code.to_string();
+        self.filter(move |record| record.iso_country().eq_ignore_ascii_case(&code))
+    }
+
+    /// Only keeps the records of this type, e.g. `AirportType::LargeAirport`
+    pub fn filter_type(self, record_type: T::Type) -> Self
+    where
+        T: HasType,
+    {
+        self.filter(move |record| record.record_type() == record_type)
+    }
+
+    /// Writes the records to a JSON array one at a time, returning how many were written.
+    /// Stops at the first record that fails to parse.
+    pub fn write_json<W: Write>(self, mut writer: W) -> Result<usize>
+    where
+        T: Serialize,
+    {
+        writer.write_all(b"[")?;
+        let mut record_count = 0;
+        for record in self.records {
+            if record_count > 0 {
+                writer.write_all(b",")?;
+            }
+            serde_json::to_writer(&mut writer, &record?)?;
+            record_count += 1;
+        }
+        writer.write_all(b"]")?;
+        writer.flush()?;
+        Ok(record_count)
+    }
+}
+
+impl<'a, T> Iterator for Pipeline<'a, T> {
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.records.next()
+    }
+}
@@ -0,0 +1,124 @@
+use crate::{distance_nm, HasCoordinates, HasCountry, HasType};
+use serde::Serialize;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// A query of records loaded once, as read by the `repl`, e.g. `country US` or `near 51 0 10`
+#[derive(Clone, Debug, PartialEq)]
+pub enum Query {
+    /// The records in the country with this code, ignoring case
+    Country(String),
+    /// The records of this type, e.g. "large_airport", ignoring case. None are of a type that isn't known.
+    Type(String),
+    /// The records within `radius_nm` nautical miles of a point, nearest first
+    Near {
+        latitude: f64,
+        longitude: f64,
+        radius_nm: f64,
+    },
+    /// The number of records
+    Count,
+}
+
+/// A record near the point of a `near` query, with its distance from the point
+#[derive(Serialize)]
+pub struct NearbyRecord<'a, T> {
+    /// Great-circle distance from the point, in nautical miles.
+    pub distance_nm: f64,
+    /// The record.
+    pub record: &'a T,
+}
+
+/// The answer to a query, serialized as a JSON array of the records or the count
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum QueryResult<'a, T> {
+    Records(Vec<&'a T>),
+    Nearby(Vec<NearbyRecord<'a, T>>),
+    Count(usize),
+}
+
+impl Query {
+    /// Answers the query from the records
+    pub fn run<'a, T>(&self, records: &'a [T]) -> QueryResult<'a, T>
+    where
+        T: HasCountry + HasType + HasCoordinates,
+    {
+        match self {
+            Query::Country(code) => QueryResult::Records(
+                records
+                    .iter()
+                    .filter(|record| record.iso_country().eq_ignore_ascii_case(code))
+                    .collect(),
+            ),
+            Query::Type(record_type) => {
+                QueryResult::Records(match record_type.parse::<T::Type>() {
+                    Ok(record_type) => records
+                        .iter()
+                        .filter(|record| record.record_type() == record_type)
+                        .collect(),
+                    Err(_) => Vec::new(),
+                })
+            }
+            Query::Near {
+                latitude,
+                longitude,
+                radius_nm,
+            } => {
+                let mut nearby: Vec<NearbyRecord<T>> = records
+                    .iter()
+                    .filter_map(|record| {
+                        let (record_latitude, record_longitude) = record.coordinates()?;
+                        let distance_nm =
+                            distance_nm(*latitude, *longitude, record_latitude, record_longitude);
+                        (distance_nm <= *radius_nm).then_some(NearbyRecord {
+                            distance_nm,
+                            record,
+                        })
+                    })
+                    .collect();
+                nearby.sort_by(|a, b| a.distance_nm.total_cmp(&b.distance_nm));
+                QueryResult::Nearby(nearby)
+            }
+            Query::Count => QueryResult::Count(records.len()),
+        }
+    }
+}
+
+/// The error when a line isn't a query
+#[derive(Debug)]
+pub struct ParseQueryError(String);
+
+impl fmt::Display for ParseQueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid query {}, expected country CODE, type TYPE, near LAT LON RADIUS_NM or count",
+            self.0
+        )
+    }
+}
+
+impl Error for ParseQueryError {}
+
+impl FromStr for Query {
+    type Err = ParseQueryError;
+
+    /// Parses a query of words separated by whitespace, such as `near 51.47 -0.46 10`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseQueryError(s.trim().to_string());
+        let words: Vec<&str> = s.split_whitespace().collect();
+        match words[..] {
+            ["country", code] => Ok(Query::Country(code.to_string())),
+            ["type", record_type] => Ok(Query::Type(record_type.to_string())),
+            ["near", latitude, longitude, radius_nm] => Ok(Query::Near {
+                latitude: latitude.parse().map_err(|_| error())?,
+                longitude: longitude.parse().map_err(|_| error())?,
+                radius_nm: radius_nm.parse().map_err(|_| error())?,
+            }),
+            ["count"] => Ok(Query::Count),
+            _ => Err(error()),
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use crate::NavaidBand;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// One of the types of airports used by OurAirports
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum AirportType {
+    #[serde(rename = "balloonport")]
+    Balloonport,
+    /// Written as "closed" in the data, although the data dictionary names it "closed_airport"
+    #[serde(rename = "closed", alias = "closed_airport")]
+    Closed,
+    #[serde(rename = "heliport")]
+    Heliport,
+    #[serde(rename = "large_airport")]
+    LargeAirport,
+    #[serde(rename = "medium_airport")]
+    MediumAirport,
+    #[serde(rename = "seaplane_base")]
+    SeaplaneBase,
+    #[serde(rename = "small_airport")]
+    SmallAirport,
+}
+
+impl AirportType {
+    /// All of the airport types
+    pub const ALL: [AirportType; 7] = [
+        AirportType::Balloonport,
+        AirportType::Closed,
+        AirportType::Heliport,
+        AirportType::LargeAirport,
+        AirportType::MediumAirport,
+        AirportType::SeaplaneBase,
+        AirportType::SmallAirport,
+    ];
+
+    /// Returns the name of the type, as used in the `type` fields
+    pub fn code(&self) -> &'static str {
+        match self {
+            AirportType::Balloonport => "balloonport",
+            AirportType::Closed => "closed",
+            AirportType::Heliport => "heliport",
+            AirportType::LargeAirport => "large_airport",
+            AirportType::MediumAirport => "medium_airport",
+            AirportType::SeaplaneBase => "seaplane_base",
+            AirportType::SmallAirport => "small_airport",
+        }
+    }
+}
+
+impl fmt::Display for AirportType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// The error when a string isn't one of the airport types
+#[derive(Debug)]
+pub struct ParseAirportTypeError(String);
+
+impl fmt::Display for ParseAirportTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown airport type {}, expected one of balloonport, closed, heliport, large_airport, \
+             medium_airport, seaplane_base or small_airport",
+            self.0
+        )
+    }
+}
+
+impl Error for ParseAirportTypeError {}
+
+impl FromStr for AirportType {
+    type Err = ParseAirportTypeError;
+
+    /// Parses an airport type, ignoring case. "closed_airport" is read as `Closed`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.eq_ignore_ascii_case("closed_airport") {
+            return Ok(AirportType::Closed);
+        }
+        AirportType::ALL
+            .iter()
+            .copied()
+            .find(|airport_type| airport_type.code().eq_ignore_ascii_case(s))
+            .ok_or_else(|| ParseAirportTypeError(s.to_string()))
+    }
+}
+
+/// One of the types of navaids used by OurAirports
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum NavaidType {
+    #[serde(rename = "DME")]
+    Dme,
+    #[serde(rename = "NDB")]
+    Ndb,
+    #[serde(rename = "NDB-DME")]
+    NdbDme,
+    #[serde(rename = "TACAN")]
+    Tacan,
+    #[serde(rename = "VOR")]
+    Vor,
+    #[serde(rename = "VOR-DME")]
+    VorDme,
+    #[serde(rename = "VORTAC")]
+    Vortac,
+}
+
+impl NavaidType {
+    /// All of the navaid types
+    pub const ALL: [NavaidType; 7] = [
+        NavaidType::Dme,
+        NavaidType::Ndb,
+        NavaidType::NdbDme,
+        NavaidType::Tacan,
+        NavaidType::Vor,
+        NavaidType::VorDme,
+        NavaidType::Vortac,
+    ];
+
+    /// Returns the name of the type, as used in the `type` fields
+    pub fn code(&self) -> &'static str {
+        match self {
+            NavaidType::Dme => "DME",
+            NavaidType::Ndb => "NDB",
+            NavaidType::NdbDme => "NDB-DME",
+            NavaidType::Tacan => "TACAN",
+            NavaidType::Vor => "VOR",
+            NavaidType::VorDme => "VOR-DME",
+            NavaidType::Vortac => "VORTAC",
+        }
+    }
+
+    /// Returns the radio band of navaids of this type, as in the data dictionary:
+    /// NDB and NDB-DME are LF/MF, VOR and VOR-DME are VHF, and DME, TACAN and VORTAC are UHF.
+    pub fn band(&self) -> NavaidBand {
+        match self {
+            NavaidType::Ndb | NavaidType::NdbDme => NavaidBand::LfMf,
+            NavaidType::Vor | NavaidType::VorDme => NavaidBand::Vhf,
+            NavaidType::Dme | NavaidType::Tacan | NavaidType::Vortac => NavaidBand::Uhf,
+        }
+    }
+}
+
+impl fmt::Display for NavaidType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+/// The error when a string isn't one of the navaid types
+#[derive(Debug)]
+pub struct ParseNavaidTypeError(String);
+
+impl fmt::Display for ParseNavaidTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown navaid type {}, expected one of DME, NDB, NDB-DME, TACAN, VOR, VOR-DME or VORTAC",
+            self.0
+        )
+    }
+}
+
+impl Error for ParseNavaidTypeError {}
+
+impl FromStr for NavaidType {
+    type Err = ParseNavaidTypeError;
+
+    /// Parses a navaid type, ignoring case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        NavaidType::ALL
+            .iter()
+            .copied()
+            .find(|navaid_type| navaid_type.code().eq_ignore_ascii_case(s))
+            .ok_or_else(|| ParseNavaidTypeError(s.to_string()))
+    }
+}
@@ -0,0 +1,84 @@
+use crate::Airport;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use strsim::jaro_winkler;
+
+/// An airport matched by a fuzzy search, with its match score
+#[derive(Serialize)]
+pub struct ScoredAirport<'a> {
+    /// Jaro-Winkler similarity between the query and the best matching field, from 0 to 1.
+    pub score: f64,
+    /// The matched airport.
+    pub airport: &'a Airport,
+}
+
+/// Returns the fields of an airport that are searched
+fn searched_fields(airport: &Airport) -> impl Iterator<Item = &str> {
+    vec![airport.name.as_str(), airport.municipality.as_str()]
+        .into_iter()
+        .chain(airport.keywords.iter().map(String::as_str))
+}
+
+/// Returns the airports whose name, municipality or keywords contain the query, ignoring case.
+pub fn search_airports<'a>(airports: &'a [Airport], query: &str) -> Vec<&'a Airport> {
+    let query = query.to_lowercase();
+    airports
+        .iter()
+        .filter(|airport| {
+            searched_fields(airport).any(|field| field.to_lowercase().contains(&query))
+        })
+        .collect()
+}
+
+/// Returns the best fuzzy match score of the query against a field, or any word in it
+fn fuzzy_score(query: &str, field: &str) -> f64 {
+    let field = field.to_lowercase();
+    field
+        .split_whitespace()
+        .map(|word| jaro_winkler(query, word))
+        .fold(jaro_winkler(query, &field), f64::max)
+}
+
+/// Ranks airports by how closely their name, municipality or keywords match the query,
+/// using Jaro-Winkler similarity, and returns the `limit` best matches with their scores.
+pub fn fuzzy_search_airports<'a>(
+    airports: &'a [Airport],
+    query: &str,
+    limit: usize,
+) -> Vec<ScoredAirport<'a>> {
+    let query = query.to_lowercase();
+    let mut results: Vec<ScoredAirport> = airports
+        .iter()
+        .map(|airport| ScoredAirport {
+            score: searched_fields(airport)
+                .map(|field| fuzzy_score(&query, field))
+                .fold(0.0, f64::max),
+            airport,
+        })
+        .collect();
+    // A stable sort, so airports with the same score stay in the order of the input
+    results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+    results.truncate(limit);
+    results
+}
+
+/// Builds an inverted index from each keyword, lowercased, to the `id`s of the records that have it.
+/// Takes the `id` and keywords of each record. Keywords are sorted, and each `id` appears once per keyword.
+pub fn keyword_index<'a>(
+    records: impl IntoIterator<Item = (&'a str, &'a [String])>,
+) -> BTreeMap<String, Vec<String>> {
+    let mut index: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (id, keywords) in records {
+        for keyword in keywords {
+            let keyword = keyword.trim().to_lowercase();
+            if keyword.is_empty() {
+                continue;
+            }
+            let ids = index.entry(keyword).or_default();
+            if !ids.iter().any(|existing| existing == id) {
+                ids.push(id.to_string());
+            }
+        }
+    }
+    index
+}
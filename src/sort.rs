@@ -0,0 +1,54 @@
+use crate::error::Result;
+use crate::Error;
+use serde::Serialize;
+use serde_json::Value;
+use std::cmp::Ordering;
+
+/// Orders two values of a field, in ascending order or descending if `descending`.
+/// Null, such as a missing elevation, is unknown rather than the smallest value, so it comes last in either order.
+pub fn cmp_field_values(a: &Value, b: &Value, descending: bool) -> Ordering {
+    let ordering = match (a, b) {
+        (Value::Null, Value::Null) => return Ordering::Equal,
+        (Value::Null, _) => return Ordering::Greater,
+        (_, Value::Null) => return Ordering::Less,
+        (Value::Number(a), Value::Number(b)) => {
+            let (a, b) = (
+                a.as_f64().unwrap_or_default(),
+                b.as_f64().unwrap_or_default(),
+            );
+            a.total_cmp(&b)
+        }
+        (Value::String(a), Value::String(b)) => a.cmp(b),
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        _ => a.to_string().cmp(&b.to_string()),
+    };
+    if descending {
+        ordering.reverse()
+    } else {
+        ordering
+    }
+}
+
+/// Sorts records by a field of their JSON output, as for `cmp_field_values`, keeping the order of records
+/// with equal values. `record` returns the record of each item, so that records paired with other data can be sorted.
+pub fn sort_by_field<R, T>(
+    records: Vec<R>,
+    field: &str,
+    descending: bool,
+    record: impl Fn(&R) -> &T,
+) -> Result<Vec<R>>
+where
+    T: Serialize,
+{
+    let mut keyed = Vec::with_capacity(records.len());
+    for item in records {
+        let key = match serde_json::to_value(record(&item))? {
+            Value::Object(mut fields) => fields.remove(field),
+            _ => None,
+        }
+        .ok_or_else(|| Error::Field(format!("No field named {}", field)))?;
+        keyed.push((key, item));
+    }
+    keyed.sort_by(|(a, _), (b, _)| cmp_field_values(a, b, descending));
+    Ok(keyed.into_iter().map(|(_, item)| item).collect())
+}
@@ -0,0 +1,565 @@
+use crate::error::Result;
+use crate::{check_columns, parse_from_reader, Error};
+use bytes::Bytes;
+use chrono::{DateTime, SecondsFormat, Utc};
+use futures::Stream;
+use log::{debug, warn};
+use reqwest::header::{HeaderMap, CONTENT_LENGTH, LAST_MODIFIED};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use zip::ZipArchive;
+
+/// Airport data URL
+const AIRPORT_URL: &str = "https://ourairports.com/data/airports.csv";
+
+/// Airport frequency data URL
+const AIRPORT_FREQUENCY_URL: &str = "https://ourairports.com/data/airport-frequencies.csv";
+
+/// Runway data URL
+const RUNWAY_URL: &str = "https://ourairports.com/data/runways.csv";
+
+/// navaid data URL
+const NAVAID_URL: &str = "https://ourairports.com/data/navaids.csv";
+
+/// country data URL
+const COUNTRY_URL: &str = "https://ourairports.com/data/countries.csv";
+
+/// region data URL
+const REGION_URL: &str = "https://ourairports.com/data/regions.csv";
+
+/// Credit for the data, for shipping with it
+pub const ATTRIBUTION: &str = "Data from OurAirports, https://ourairports.com/data/";
+
+/// The license of the data
+pub const LICENSE: &str = "Public Domain, https://ourairports.com/about.html";
+
+/// Request data type
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RequestType {
+    Airport,
+    AirportFrequency,
+    Runway,
+    Navaid,
+    Country,
+    Region,
+}
+
+impl RequestType {
+    /// All of the data types
+    pub const ALL: [RequestType; 6] = [
+        RequestType::Airport,
+        RequestType::AirportFrequency,
+        RequestType::Runway,
+        RequestType::Navaid,
+        RequestType::Country,
+        RequestType::Region,
+    ];
+
+    /// Returns the name of the dataset, as used for keys in combined output
+    pub fn name(&self) -> &'static str {
+        match self {
+            RequestType::Airport => "airports",
+            RequestType::AirportFrequency => "frequencies",
+            RequestType::Runway => "runways",
+            RequestType::Navaid => "navaids",
+            RequestType::Country => "countries",
+            RequestType::Region => "regions",
+        }
+    }
+
+    /// Returns the URL to download the data from
+    pub fn url(&self) -> &'static str {
+        match self {
+            RequestType::Airport => AIRPORT_URL,
+            RequestType::AirportFrequency => AIRPORT_FREQUENCY_URL,
+            RequestType::Runway => RUNWAY_URL,
+            RequestType::Navaid => NAVAID_URL,
+            RequestType::Country => COUNTRY_URL,
+            RequestType::Region => REGION_URL,
+        }
+    }
+
+    /// Returns the columns that the data must have, in the order published on the website.
+    /// Optional columns of newer dumps of the data aren't included.
+    pub fn columns(&self) -> &'static [&'static str] {
+        match self {
+            RequestType::Airport => &[
+                "id",
+                "ident",
+                "type",
+                "name",
+                "latitude_deg",
+                "longitude_deg",
+                "elevation_ft",
+                "continent",
+                "iso_country",
+                "iso_region",
+                "municipality",
+                "scheduled_service",
+                "gps_code",
+                "iata_code",
+                "local_code",
+                "home_link",
+                "wikipedia_link",
+                "keywords",
+            ],
+            RequestType::AirportFrequency => &[
+                "id",
+                "airport_ref",
+                "airport_ident",
+                "type",
+                "description",
+                "frequency_mhz",
+            ],
+            RequestType::Runway => &[
+                "id",
+                "airport_ref",
+                "airport_ident",
+                "length_ft",
+                "width_ft",
+                "surface",
+                "lighted",
+                "closed",
+                "le_ident",
+                "le_latitude_deg",
+                "le_longitude_deg",
+                "le_elevation_ft",
+                "le_heading_degT",
+                "le_displaced_threshold_ft",
+                "he_ident",
+                "he_latitude_deg",
+                "he_longitude_deg",
+                "he_elevation_ft",
+                "he_heading_degT",
+                "he_displaced_threshold_ft",
+            ],
+            RequestType::Navaid => &[
+                "id",
+                "filename",
+                "ident",
+                "name",
+                "type",
+                "frequency_khz",
+                "latitude_deg",
+                "longitude_deg",
+                "elevation_ft",
+                "iso_country",
+                "dme_frequency_khz",
+                "dme_channel",
+                "dme_latitude_deg",
+                "dme_longitude_deg",
+                "dme_elevation_ft",
+                "slaved_variation_deg",
+                "magnetic_variation_deg",
+                "usageType",
+                "power",
+                "associated_airport",
+            ],
+            RequestType::Country => &[
+                "id",
+                "code",
+                "name",
+                "continent",
+                "wikipedia_link",
+                "keywords",
+            ],
+            RequestType::Region => &[
+                "id",
+                "code",
+                "local_code",
+                "name",
+                "continent",
+                "iso_country",
+                "wikipedia_link",
+                "keywords",
+            ],
+        }
+    }
+
+    /// Returns the name of the data file, as published on the website
+    pub fn filename(&self) -> &'static str {
+        match self {
+            RequestType::Airport => "airports.csv",
+            RequestType::AirportFrequency => "airport-frequencies.csv",
+            RequestType::Runway => "runways.csv",
+            RequestType::Navaid => "navaids.csv",
+            RequestType::Country => "countries.csv",
+            RequestType::Region => "regions.csv",
+        }
+    }
+}
+
+/// Where the data was read from, and how fresh it is
+#[derive(Serialize)]
+pub struct Source {
+    /// The URL the data was downloaded from, or the path of the local file.
+    pub url: String,
+    /// The `Last-Modified` header of the download, or the modification time of the local file.
+    /// None if unavailable.
+    pub last_modified: Option<String>,
+    /// The time the data was read.
+    pub retrieved_at: String,
+}
+
+/// How fresh a dataset on the internet is, without downloading it
+#[derive(Serialize)]
+pub struct RemoteInfo {
+    /// The URL of the data.
+    pub url: String,
+    /// The `Last-Modified` header of the data. None if unavailable.
+    pub last_modified: Option<String>,
+    /// The `Content-Length` header of the data, in bytes. None if unavailable.
+    pub content_length: Option<u64>,
+}
+
+/// Formats a timestamp as RFC 3339
+fn format_time(time: DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Returns the `Last-Modified` header as RFC 3339, if available and valid
+fn last_modified(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(LAST_MODIFIED)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| DateTime::parse_from_rfc2822(value).ok())
+        .map(|time| format_time(time.with_timezone(&Utc)))
+}
+
+/// Decodes the data as UTF-8, replacing any invalid bytes with U+FFFD rather than failing,
+/// and warning how many invalid sequences were replaced
+fn decode_text(bytes: Vec<u8>, origin: &str) -> String {
+    String::from_utf8(bytes).unwrap_or_else(|err| {
+        let bytes = err.into_bytes();
+        let replaced_count = bytes
+            .utf8_chunks()
+            .filter(|chunk| !chunk.invalid().is_empty())
+            .count();
+        warn!(
+            "Replaced {} invalid UTF-8 sequences in {}",
+            replaced_count, origin
+        );
+        String::from_utf8_lossy(&bytes).into_owned()
+    })
+}
+
+/// Returns the modification time of a local file as RFC 3339, if available
+fn file_modified(path: &Path) -> Option<String> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .map(|time| format_time(time.into()))
+}
+
+/// Returns whether the path is of a ZIP archive, by its extension
+fn is_archive(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("zip"))
+}
+
+/// Returns the file to read a dataset from, given a directory of data files named as on the website
+/// or a ZIP archive of them
+pub fn dataset_file(input: &Path, request_type: RequestType) -> PathBuf {
+    if is_archive(input) {
+        input.to_path_buf()
+    } else {
+        input.join(request_type.filename())
+    }
+}
+
+/// Reads the csv data from a local file, a ZIP archive or the internet,
+/// checking that it has the columns of the data type
+pub async fn read_text(
+    file_path: &Option<PathBuf>,
+    request_type: RequestType,
+) -> Result<(String, Source)> {
+    let (content, source) = match file_path {
+        Some(path) if is_archive(path) => read_archive(path, request_type)?,
+        Some(path) => read_file(path)?,
+        None => download_text(request_type.url()).await?,
+    };
+    check_columns(content.as_bytes(), request_type)?;
+    Ok((content, source))
+}
+
+/// Reads the csv data from a local file
+pub fn read_file(path: &Path) -> Result<(String, Source)> {
+    let retrieved_at = format_time(Utc::now());
+    debug!("Reading file {}", path.to_string_lossy());
+    let bytes = fs::read(path).map_err(|err| Error::File(path.to_path_buf(), err))?;
+    let content = decode_text(bytes, &path.to_string_lossy());
+    let source = Source {
+        url: path.to_string_lossy().into_owned(),
+        last_modified: file_modified(path),
+        retrieved_at,
+    };
+    Ok((content, source))
+}
+
+/// Reads the csv data from the entry of a ZIP archive named as the data file on the website,
+/// in any folder of the archive
+pub fn read_archive(path: &Path, request_type: RequestType) -> Result<(String, Source)> {
+    let retrieved_at = format_time(Utc::now());
+    debug!(
+        "Reading {} from archive {}",
+        request_type.filename(),
+        path.to_string_lossy()
+    );
+    let file_error = |err: io::Error| Error::File(path.to_path_buf(), err);
+    let file = File::open(path).map_err(file_error)?;
+    let mut archive = ZipArchive::new(file).map_err(|err| file_error(err.into()))?;
+    let entry_name = archive
+        .file_names()
+        .find(|name| name.rsplit('/').next() == Some(request_type.filename()))
+        .map(str::to_string)
+        .ok_or_else(|| {
+            file_error(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no {} in the archive", request_type.filename()),
+            ))
+        })?;
+    let mut bytes = Vec::new();
+    archive
+        .by_name(&entry_name)
+        .map_err(|err| file_error(err.into()))?
+        .read_to_end(&mut bytes)
+        .map_err(file_error)?;
+    let url = path.join(&entry_name).to_string_lossy().into_owned();
+    let content = decode_text(bytes, &url);
+    let source = Source {
+        url,
+        last_modified: file_modified(path),
+        retrieved_at,
+    };
+    Ok((content, source))
+}
+
+/// Returns the length of the complete rows at the start of csv data: up to the last newline
+/// that isn't inside a quoted field
+fn complete_rows_len(bytes: &[u8]) -> usize {
+    let mut in_quotes = false;
+    let mut len = 0;
+    for (i, byte) in bytes.iter().enumerate() {
+        match byte {
+            b'"' => in_quotes = !in_quotes,
+            b'\n' if !in_quotes => len = i + 1,
+            _ => {}
+        }
+    }
+    len
+}
+
+/// Downloads the csv data from the given URL
+pub async fn download_text(url: &str) -> Result<(String, Source)> {
+    download_text_limited(url, None).await
+}
+
+/// Downloads the csv data from the given URL, reading at most `max_bytes` of it if given.
+/// If the data is cut off, the last, partial row is dropped, so only complete rows remain.
+pub async fn download_text_limited(
+    url: &str,
+    max_bytes: Option<usize>,
+) -> Result<(String, Source)> {
+    let retrieved_at = format_time(Utc::now());
+    debug!("Downloading from {}", url);
+    let network_error = |err| Error::Network(url.to_string(), err);
+    let mut resp = reqwest::get(url)
+        .await
+        .and_then(|resp| resp.error_for_status())
+        .map_err(network_error)?;
+    let last_modified = last_modified(resp.headers());
+    let source = Source {
+        url: url.to_string(),
+        last_modified,
+        retrieved_at,
+    };
+    let bytes = match max_bytes {
+        Some(max_bytes) => {
+            let mut bytes = Vec::new();
+            while bytes.len() <= max_bytes {
+                match resp.chunk().await.map_err(network_error)? {
+                    Some(chunk) => bytes.extend_from_slice(&chunk),
+                    None => break,
+                }
+            }
+            if bytes.len() > max_bytes {
+                bytes.truncate(max_bytes);
+                bytes.truncate(complete_rows_len(&bytes));
+                debug!("Stopped downloading after {} bytes", max_bytes);
+            }
+            bytes
+        }
+        None => resp.bytes().await.map_err(network_error)?.to_vec(),
+    };
+    let text = decode_text(bytes, url);
+    Ok((text, source))
+}
+
+/// Records of csv data parsed while it is downloaded, from `download_records`
+pub struct RecordStream<T> {
+    url: String,
+    request_type: RequestType,
+    body: Pin<Box<dyn Stream<Item = reqwest::Result<Bytes>> + Send>>,
+    /// The header row, once it has been downloaded
+    header: Option<Vec<u8>>,
+    /// The downloaded data after the last complete row
+    buffer: Vec<u8>,
+    /// How many lines of the data have been parsed
+    lines: u64,
+    records: VecDeque<Result<T>>,
+    finished: bool,
+}
+
+// the records are never pinned, only the body they're parsed from, which is boxed
+impl<T> Unpin for RecordStream<T> {}
+
+impl<T> Stream for RecordStream<T>
+where
+    T: DeserializeOwned,
+{
+    type Item = Result<T>;
+
+    /// Returns the next record, downloading more of the data as needed, or `None` at the end of the data.
+    /// After an error, no more records are returned.
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<T>>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(record) = this.records.pop_front() {
+                return Poll::Ready(Some(record));
+            }
+            if this.finished {
+                return Poll::Ready(None);
+            }
+            match ready!(this.body.as_mut().poll_next(cx)) {
+                Some(Ok(chunk)) => {
+                    this.buffer.extend_from_slice(&chunk);
+                    this.parse_rows(complete_rows_len(&this.buffer));
+                }
+                None => {
+                    this.finished = true;
+                    if this.header.is_none() && this.buffer.is_empty() {
+                        // the data has no header row at all
+                        return Poll::Ready(check_columns(&[], this.request_type).err().map(Err));
+                    }
+                    this.parse_rows(this.buffer.len());
+                }
+                Some(Err(err)) => {
+                    this.finished = true;
+                    return Poll::Ready(Some(Err(Error::Network(this.url.clone(), err))));
+                }
+            }
+        }
+    }
+}
+
+impl<T> RecordStream<T>
+where
+    T: DeserializeOwned,
+{
+    /// Parses the first `len` bytes of the buffer, which are complete rows, into records
+    fn parse_rows(&mut self, len: usize) {
+        if len == 0 {
+            return;
+        }
+        let rows: Vec<u8> = self.buffer.drain(..len).collect();
+        let lines = self.lines;
+        self.lines += rows.iter().filter(|byte| **byte == b'\n').count() as u64;
+        let (batch, line_offset) = match &self.header {
+            // the header row is parsed again with each batch, so that fields are matched by column
+            Some(header) => ([header.as_slice(), &rows].concat(), lines - 1),
+            None => {
+                if let Err(err) = check_columns(&rows, self.request_type) {
+                    self.records.push_back(Err(err));
+                    self.finished = true;
+                    return;
+                }
+                let header_len = rows
+                    .iter()
+                    .position(|byte| *byte == b'\n')
+                    .map_or(rows.len(), |i| i + 1);
+                self.header = Some(rows[..header_len].to_vec());
+                (rows, 0)
+            }
+        };
+        // blank rows are skipped, as for parse_from_reader
+        for record in parse_from_reader(batch.as_slice()) {
+            let record = record.map_err(|err| match err {
+                Error::Deserialize { line, source } => Error::Deserialize {
+                    line: line + line_offset,
+                    source,
+                },
+                err => err,
+            });
+            let failed = record.is_err();
+            self.records.push_back(record);
+            if failed {
+                self.finished = true;
+                break;
+            }
+        }
+    }
+}
+
+/// Downloads the csv data from the given URL and parses it into records as it arrives, as a `Stream` of them,
+/// so that the first records are available before the download finishes and the data is never
+/// held in memory as a whole. The columns are checked against the data type once the header row arrives.
+///
+/// The data is not decoded upfront, so unlike `download_text`, invalid UTF-8 in a field is a parse error
+/// of that record instead of being replaced.
+pub async fn download_records<T>(
+    url: &str,
+    request_type: RequestType,
+) -> Result<(RecordStream<T>, Source)>
+where
+    T: DeserializeOwned,
+{
+    let retrieved_at = format_time(Utc::now());
+    debug!("Streaming records from {}", url);
+    let resp = reqwest::get(url)
+        .await
+        .and_then(|resp| resp.error_for_status())
+        .map_err(|err| Error::Network(url.to_string(), err))?;
+    let source = Source {
+        url: url.to_string(),
+        last_modified: last_modified(resp.headers()),
+        retrieved_at,
+    };
+    let records = RecordStream {
+        url: url.to_string(),
+        request_type,
+        body: Box::pin(resp.bytes_stream()),
+        header: None,
+        buffer: Vec::new(),
+        lines: 0,
+        records: VecDeque::new(),
+        finished: false,
+    };
+    Ok((records, source))
+}
+
+/// Fetches how fresh the data at the given URL is with a HEAD request, without downloading it
+pub async fn fetch_info(url: &str) -> Result<RemoteInfo> {
+    debug!("Requesting headers of {}", url);
+    let resp = reqwest::Client::new()
+        .head(url)
+        .send()
+        .await
+        .and_then(|resp| resp.error_for_status())
+        .map_err(|err| Error::Network(url.to_string(), err))?;
+    let content_length = resp
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok());
+    Ok(RemoteInfo {
+        url: url.to_string(),
+        last_modified: last_modified(resp.headers()),
+        content_length,
+    })
+}
@@ -0,0 +1,74 @@
+use crate::{Airport, Runway};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A coarse category of aircraft, by the runway length it needs
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AircraftCategory {
+    /// Wide-body jets, such as the Boeing 777.
+    HeavyJet,
+    /// Regional and narrow-body jets, such as the Embraer E175.
+    RegionalJet,
+    /// General aviation, such as light piston aircraft.
+    Ga,
+}
+
+/// The shortest runway lengths, in feet, suitable for each category of aircraft
+#[derive(Clone, Copy, Debug)]
+pub struct RunwayThresholds {
+    /// The shortest runway suitable for heavy jets.
+    pub heavy_jet_ft: u32,
+    /// The shortest runway suitable for regional jets.
+    pub regional_jet_ft: u32,
+}
+
+impl Default for RunwayThresholds {
+    /// 8,000 ft for heavy jets and 5,000 ft for regional jets
+    fn default() -> Self {
+        RunwayThresholds {
+            heavy_jet_ft: 8000,
+            regional_jet_ft: 5000,
+        }
+    }
+}
+
+impl RunwayThresholds {
+    /// Returns the largest category of aircraft a runway of the given length is suitable for
+    pub fn category(&self, length_ft: u32) -> AircraftCategory {
+        if length_ft >= self.heavy_jet_ft {
+            AircraftCategory::HeavyJet
+        } else if length_ft >= self.regional_jet_ft {
+            AircraftCategory::RegionalJet
+        } else {
+            AircraftCategory::Ga
+        }
+    }
+}
+
+/// Maps the `id` of each airport to the length of its longest open runway, in feet.
+/// Closed runways and runways without a length are left out.
+pub fn max_runway_lengths(runways: &[Runway]) -> HashMap<String, u32> {
+    let mut lengths: HashMap<String, u32> = HashMap::new();
+    for runway in runways.iter().filter(|runway| !runway.closed) {
+        if let Some(length_ft) = runway.length_ft {
+            let max_length = lengths.entry(runway.airport_ref.clone()).or_default();
+            *max_length = (*max_length).max(length_ft);
+        }
+    }
+    lengths
+}
+
+impl Airport {
+    /// Adds the `max_runway_length_ft` and `suitable_for` fields, from the longest open runway of the airport.
+    /// Both are set to null if the airport isn't in the map.
+    pub fn add_runway_suitability(
+        &mut self,
+        max_runway_lengths: &HashMap<String, u32>,
+        thresholds: &RunwayThresholds,
+    ) {
+        let max_length = max_runway_lengths.get(&self.id).copied();
+        self.max_runway_length_ft = Some(max_length);
+        self.suitable_for = Some(max_length.map(|length_ft| thresholds.category(length_ft)));
+    }
+}
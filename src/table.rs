@@ -0,0 +1,191 @@
+use crate::error::Result;
+use serde_json::{json, Value};
+use std::io;
+
+/// Formats a field of a record as a table cell.
+/// Arrays (such as `keywords`) are joined with `array_delimiter` if given, and written as JSON otherwise.
+fn cell(value: &Value, array_delimiter: Option<&str>) -> Result<String> {
+    Ok(match value {
+        Value::Null => String::new(),
+        Value::String(string) => string.clone(),
+        Value::Array(items) => match array_delimiter {
+            Some(delimiter) => items
+                .iter()
+                .map(|item| cell(item, None))
+                .collect::<Result<Vec<_>>>()?
+                .join(delimiter),
+            None => serde_json::to_string(value)?,
+        },
+        Value::Object(_) => serde_json::to_string(value)?,
+        Value::Bool(_) | Value::Number(_) => value.to_string(),
+    })
+}
+
+/// Returns the fields of every record serialized as a JSON object, in the order they are first seen,
+/// so that a field left out of some records still has a column
+fn columns(records: &[Value]) -> Vec<&String> {
+    let mut columns: Vec<&String> = Vec::new();
+    for record in records {
+        if let Value::Object(fields) = record {
+            for key in fields.keys() {
+                if !columns.contains(&key) {
+                    columns.push(key);
+                }
+            }
+        }
+    }
+    columns
+}
+
+/// Writes records serialized as JSON objects as a delimited table, with a header row from the fields of the records.
+/// Arrays (such as `keywords`) are joined with `array_delimiter` if given, and written as JSON otherwise.
+pub fn to_delimited(
+    records: &[Value],
+    delimiter: u8,
+    array_delimiter: Option<&str>,
+) -> Result<String> {
+    // rows always end in \n, whatever the platform, so that tables are the same everywhere
+    let mut wtr = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .terminator(csv::Terminator::Any(b'\n'))
+        .from_writer(Vec::new());
+    let header = columns(records);
+    if !header.is_empty() {
+        wtr.write_record(&header)?;
+    }
+    for record in records {
+        let row = header
+            .iter()
+            .map(|key| {
+                cell(
+                    record.get(key.as_str()).unwrap_or(&Value::Null),
+                    array_delimiter,
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+        wtr.write_record(&row)?;
+    }
+    into_string(wtr)
+}
+
+/// Finishes writing csv to memory, returning what was written
+pub(crate) fn into_string(wtr: csv::Writer<Vec<u8>>) -> Result<String> {
+    let bytes = wtr
+        .into_inner()
+        .map_err(|err| io::Error::new(err.error().kind(), err.error().to_string()))?;
+    Ok(String::from_utf8(bytes).expect("csv only writes valid UTF-8 from strings"))
+}
+
+/// Converts records serialized as JSON objects into an object of `columns`, the fields of the records,
+/// and `rows`, an array of the values of each record in the order of the columns, null where a record lacks a field
+pub fn to_tuples(records: &[Value]) -> Value {
+    let columns = columns(records);
+    let rows: Vec<Vec<&Value>> = records
+        .iter()
+        .map(|record| {
+            columns
+                .iter()
+                .map(|column| record.get(column.as_str()).unwrap_or(&Value::Null))
+                .collect()
+        })
+        .collect();
+    json!({
+        "columns": columns,
+        "rows": rows,
+    })
+}
+
+/// Returns an SQL identifier for a name, quoted unless it's plain lowercase letters, digits and underscores
+fn sql_identifier(name: &str) -> String {
+    let is_plain = name.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+    if is_plain {
+        name.to_string()
+    } else {
+        format!("\"{}\"", name.replace('"', "\"\""))
+    }
+}
+
+/// Returns the SQL type of a column from its values: BIGINT if they are all integers, DOUBLE PRECISION if they are
+/// all numbers, BOOLEAN if they are all booleans, and TEXT otherwise. Nulls are ignored.
+fn sql_type<'a>(values: impl Iterator<Item = &'a Value>) -> &'static str {
+    let mut sql_type = None;
+    for value in values {
+        let value_type = match value {
+            Value::Null => continue,
+            Value::Number(number) if number.is_i64() || number.is_u64() => "BIGINT",
+            Value::Number(_) => "DOUBLE PRECISION",
+            Value::Bool(_) => "BOOLEAN",
+            _ => "TEXT",
+        };
+        sql_type = match (sql_type, value_type) {
+            (None, value_type) => Some(value_type),
+            (Some("BIGINT"), "DOUBLE PRECISION") | (Some("DOUBLE PRECISION"), "BIGINT") => {
+                Some("DOUBLE PRECISION")
+            }
+            (Some(sql_type), value_type) if sql_type == value_type => Some(sql_type),
+            _ => Some("TEXT"),
+        };
+    }
+    sql_type.unwrap_or("TEXT")
+}
+
+/// Formats a field of a record as an SQL literal, with strings quoted and their single quotes doubled.
+/// Arrays (such as `keywords`) are written as text, as for a table cell.
+fn sql_literal(value: &Value, array_delimiter: Option<&str>) -> Result<String> {
+    Ok(match value {
+        Value::Null => "NULL".to_string(),
+        Value::Bool(true) => "TRUE".to_string(),
+        Value::Bool(false) => "FALSE".to_string(),
+        Value::Number(number) => number.to_string(),
+        _ => format!("'{}'", cell(value, array_delimiter)?.replace('\'', "''")),
+    })
+}
+
+/// Writes records serialized as JSON objects as an SQL script: a `CREATE TABLE` statement with a column for each field
+/// of the records, typed from the values of every record, followed by an `INSERT` statement for each record.
+/// Arrays (such as `keywords`) are joined with `array_delimiter` if given, and written as JSON otherwise.
+/// The script is empty if there are no fields, such as when there are no records, as a table needs a column.
+pub fn to_sql(records: &[Value], table: &str, array_delimiter: Option<&str>) -> Result<String> {
+    let columns = columns(records);
+    if columns.is_empty() {
+        return Ok(String::new());
+    }
+    let table = sql_identifier(table);
+    let column_list = columns
+        .iter()
+        .map(|column| sql_identifier(column))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let column_definitions = columns
+        .iter()
+        .map(|column| {
+            let values = records
+                .iter()
+                .filter_map(|record| record.get(column.as_str()));
+            format!("  {} {}", sql_identifier(column), sql_type(values))
+        })
+        .collect::<Vec<_>>()
+        .join(",\n");
+
+    let mut script = format!("CREATE TABLE {} (\n{}\n);\n", table, column_definitions);
+    for record in records {
+        let values = columns
+            .iter()
+            .map(|column| {
+                sql_literal(
+                    record.get(column.as_str()).unwrap_or(&Value::Null),
+                    array_delimiter,
+                )
+            })
+            .collect::<Result<Vec<_>>>()?
+            .join(", ");
+        script.push_str(&format!(
+            "INSERT INTO {} ({}) VALUES ({});\n",
+            table, column_list, values
+        ));
+    }
+    Ok(script)
+}
@@ -0,0 +1,265 @@
+use crate::country_bounds::{country_bounds, CountryBounds};
+use crate::{
+    is_unassigned_region, Airport, AirportFrequency, Country, Fix, Navaid, Region, Runway,
+};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::ops::RangeInclusive;
+
+/// A code, and how many records use it
+#[derive(Serialize)]
+pub struct CodeCount {
+    pub code: String,
+    pub count: usize,
+}
+
+/// A frequency outside the civil aviation VHF band
+#[derive(Serialize)]
+pub struct FrequencyIssue {
+    /// The `id` of the frequency.
+    pub id: String,
+    /// The `ident` of the airport of the frequency.
+    pub airport_ident: String,
+    /// The frequency, as in the data.
+    pub frequency_mhz: String,
+}
+
+/// An airport far from its country
+#[derive(Serialize)]
+pub struct LocationIssue {
+    /// The `ident` of the airport.
+    pub ident: String,
+    /// The `iso_country` of the airport.
+    pub iso_country: String,
+    /// Roughly how far the airport is outside the bounds of its country, in nautical miles.
+    pub distance_nm: f64,
+}
+
+/// An airport or navaid whose latitude and longitude look swapped
+#[derive(Serialize)]
+pub struct CoordinateIssue {
+    /// The dataset of the record: "airport" or "navaid".
+    pub record_type: &'static str,
+    /// The `id` of the record.
+    pub id: String,
+    /// The `ident` of the record.
+    pub ident: String,
+    /// The `iso_country` of the record.
+    pub iso_country: String,
+    /// The latitude, as in the data.
+    pub latitude_deg: f64,
+    /// The longitude, as in the data.
+    pub longitude_deg: f64,
+    /// The suggested latitude: the longitude in the data.
+    pub suggested_latitude_deg: f64,
+    /// The suggested longitude: the latitude in the data.
+    pub suggested_longitude_deg: f64,
+}
+
+/// Issues found while validating the data
+#[derive(Default, Serialize)]
+pub struct ValidationReport {
+    /// `iso_country` codes of airports, navaids and regions that don't appear in the country data.
+    pub unknown_country_codes: Vec<CodeCount>,
+    /// `iso_region` codes of airports that don't appear in the region data,
+    /// except for the unassigned pseudo regions ("U-A").
+    pub unknown_region_codes: Vec<CodeCount>,
+    /// `id`s of runways whose two ends aren't roughly opposite each other.
+    pub mismatched_runway_headings: Vec<String>,
+    /// Airport frequencies outside the civil aviation VHF band, or not a number. None if not checked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub out_of_band_frequencies: Option<Vec<FrequencyIssue>>,
+    /// Airports too far outside the bounds of their country to be in it. None if not checked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub misplaced_airports: Option<Vec<LocationIssue>>,
+    /// Airports and navaids whose latitude and longitude look swapped. None if not checked.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub swapped_coordinates: Option<Vec<CoordinateIssue>>,
+    /// The corrections made to each data file before checking it, by file name. None if not fixed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fixes: Option<BTreeMap<String, Vec<Fix>>>,
+}
+
+impl ValidationReport {
+    /// Returns whether no issues were found
+    pub fn is_valid(&self) -> bool {
+        self.unknown_country_codes.is_empty()
+            && self.unknown_region_codes.is_empty()
+            && self.mismatched_runway_headings.is_empty()
+            && self
+                .out_of_band_frequencies
+                .as_ref()
+                .is_none_or(Vec::is_empty)
+            && self.misplaced_airports.as_ref().is_none_or(Vec::is_empty)
+            && self.swapped_coordinates.as_ref().is_none_or(Vec::is_empty)
+    }
+}
+
+/// Finds the `iso_country` codes of airports, navaids and regions that don't match the `code` of any country,
+/// except for the allowed codes, sorted by code.
+pub fn unknown_country_codes(
+    countries: &[Country],
+    airports: &[Airport],
+    navaids: &[Navaid],
+    regions: &[Region],
+    allowed_codes: &[String],
+) -> Vec<CodeCount> {
+    let known_codes: HashSet<&str> = countries
+        .iter()
+        .map(|country| country.code.as_str())
+        .chain(allowed_codes.iter().map(String::as_str))
+        .collect();
+
+    let used_codes = airports
+        .iter()
+        .map(|airport| airport.iso_country.as_str())
+        .chain(navaids.iter().map(|navaid| navaid.iso_country.as_str()))
+        .chain(regions.iter().map(|region| region.iso_country.as_str()));
+
+    count_codes(used_codes.filter(|code| !known_codes.contains(code)))
+}
+
+/// Finds the `iso_region` codes of airports that don't match the `code` of any region, sorted by code.
+/// The unassigned pseudo regions ("U-A") are always allowed, as they aren't real regions.
+pub fn unknown_region_codes(regions: &[Region], airports: &[Airport]) -> Vec<CodeCount> {
+    let known_codes: HashSet<&str> = regions.iter().map(|region| region.code.as_str()).collect();
+    count_codes(
+        airports
+            .iter()
+            .map(|airport| airport.iso_region.as_str())
+            .filter(|code| !known_codes.contains(code) && !is_unassigned_region(code)),
+    )
+}
+
+/// Counts how many times each code appears, sorted by code
+fn count_codes<'a>(codes: impl Iterator<Item = &'a str>) -> Vec<CodeCount> {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for code in codes {
+        *counts.entry(code).or_default() += 1;
+    }
+
+    counts
+        .into_iter()
+        .map(|(code, count)| CodeCount {
+            code: code.to_string(),
+            count,
+        })
+        .collect()
+}
+
+/// Finds the `id`s of runways whose `le_heading_deg_true` and `he_heading_deg_true`
+/// are more than `tolerance` degrees away from being 180 degrees apart.
+/// Runways missing either heading are skipped.
+pub fn mismatched_runway_headings(runways: &[Runway], tolerance: f64) -> Vec<String> {
+    runways
+        .iter()
+        .filter(|runway| runway.headings_are_reciprocal(tolerance) == Some(false))
+        .map(|runway| runway.id.clone())
+        .collect()
+}
+
+/// The civil aviation VHF band, in megahertz
+const AVIATION_BAND_MHZ: RangeInclusive<f64> = 118.0..=136.975;
+
+/// Finds the airport frequencies whose `frequency_mhz` isn't a number
+/// in the civil aviation VHF band of 118 to 136.975 MHz.
+pub fn out_of_band_frequencies(frequencies: &[AirportFrequency]) -> Vec<FrequencyIssue> {
+    frequencies
+        .iter()
+        .filter(|frequency| {
+            frequency
+                .frequency_mhz
+                .trim()
+                .parse::<f64>()
+                .map_or(true, |mhz| !AVIATION_BAND_MHZ.contains(&mhz))
+        })
+        .map(|frequency| FrequencyIssue {
+            id: frequency.id.clone(),
+            airport_ident: frequency.airport_ident.clone(),
+            frequency_mhz: frequency.frequency_mhz.clone(),
+        })
+        .collect()
+}
+
+/// How far outside the bounds of its country an airport must be to be misplaced, in nautical miles,
+/// allowing for the bounds being coarse
+const MISPLACED_DISTANCE_NM: f64 = 100.0;
+
+/// Returns whether a point is close enough to the bounds of a country to be in it
+fn is_in_country(bounds: &CountryBounds, latitude: f64, longitude: f64) -> bool {
+    bounds.distance_nm(latitude, longitude) <= MISPLACED_DISTANCE_NM
+}
+
+/// Finds the airports grossly far from their `iso_country`, such as ones labelled with the wrong country
+/// or with their latitude and longitude swapped.
+///
+/// This is a coarse check against a table of the bounding box of each country, including its outlying islands:
+/// an airport is misplaced if it's more than 100 NM outside the bounds of its country. Airports without coordinates,
+/// or in a country that isn't in the table, are skipped.
+pub fn misplaced_airports(airports: &[Airport]) -> Vec<LocationIssue> {
+    airports
+        .iter()
+        .filter_map(|airport| {
+            let (latitude, longitude) = (airport.latitude_deg?, airport.longitude_deg?);
+            let country = country_bounds(&airport.iso_country)?;
+            (!is_in_country(&country, latitude, longitude)).then(|| LocationIssue {
+                ident: airport.ident.clone(),
+                iso_country: airport.iso_country.clone(),
+                distance_nm: (country.distance_nm(latitude, longitude) * 10.0).round() / 10.0,
+            })
+        })
+        .collect()
+}
+
+/// Returns an issue for a record if its latitude and longitude look swapped: the latitude is impossible,
+/// or swapping the two would move it from outside its country to inside it
+fn swapped_coordinates_issue(
+    record_type: &'static str,
+    id: &str,
+    ident: &str,
+    iso_country: &str,
+    coordinates: (Option<f64>, Option<f64>),
+) -> Option<CoordinateIssue> {
+    let (latitude, longitude) = (coordinates.0?, coordinates.1?);
+    let is_swapped = !(-90.0..=90.0).contains(&latitude)
+        || ((-90.0..=90.0).contains(&longitude)
+            && country_bounds(iso_country).is_some_and(|country| {
+                !is_in_country(&country, latitude, longitude)
+                    && is_in_country(&country, longitude, latitude)
+            }));
+    is_swapped.then(|| CoordinateIssue {
+        record_type,
+        id: id.to_string(),
+        ident: ident.to_string(),
+        iso_country: iso_country.to_string(),
+        latitude_deg: latitude,
+        longitude_deg: longitude,
+        suggested_latitude_deg: longitude,
+        suggested_longitude_deg: latitude,
+    })
+}
+
+/// Finds the airports and navaids whose `latitude_deg` and `longitude_deg` look swapped: where the latitude is
+/// outside -90 to 90, or where swapping the two would place the record within the coarse bounds of its country,
+/// as for `misplaced_airports`, when it's outside them now. Records without coordinates are skipped.
+pub fn swapped_coordinates(airports: &[Airport], navaids: &[Navaid]) -> Vec<CoordinateIssue> {
+    let airport_issues = airports.iter().filter_map(|airport| {
+        swapped_coordinates_issue(
+            "airport",
+            &airport.id,
+            &airport.ident,
+            &airport.iso_country,
+            (airport.latitude_deg, airport.longitude_deg),
+        )
+    });
+    let navaid_issues = navaids.iter().filter_map(|navaid| {
+        swapped_coordinates_issue(
+            "navaid",
+            &navaid.id,
+            &navaid.ident,
+            &navaid.iso_country,
+            (navaid.latitude_deg, navaid.longitude_deg),
+        )
+    });
+    airport_issues.chain(navaid_issues).collect()
+}
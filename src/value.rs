@@ -0,0 +1,30 @@
+use crate::ourairports::from_json_value;
+use crate::{Airport, AirportFrequency, Country, Error, Navaid, Region, Runway};
+use serde_json::Value;
+use std::convert::TryFrom;
+
+/// Implements conversions between records and `serde_json::Value`.
+///
+/// The value has the same shape as the record's JSON output,
+/// so a record converted to a value can be converted back losslessly.
+macro_rules! impl_value_conversions {
+    ($($record:ty),*) => {
+        $(
+            impl From<&$record> for Value {
+                fn from(record: &$record) -> Self {
+                    serde_json::to_value(record).expect("records always serialize to JSON")
+                }
+            }
+
+            impl TryFrom<Value> for $record {
+                type Error = Error;
+
+                fn try_from(value: Value) -> Result<Self, Self::Error> {
+                    from_json_value(value)
+                }
+            }
+        )*
+    };
+}
+
+impl_value_conversions!(Airport, AirportFrequency, Runway, Navaid, Country, Region);
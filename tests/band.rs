@@ -0,0 +1,14 @@
+use ourairports::{navaid_band, NavaidBand};
+
+#[test]
+fn bands_by_type_then_frequency() {
+    assert_eq!(navaid_band("NDB-DME", "338"), Some(NavaidBand::LfMf));
+    assert_eq!(navaid_band("VOR", "115300"), Some(NavaidBand::Vhf));
+    assert_eq!(navaid_band("VORTAC", "115300"), Some(NavaidBand::Uhf));
+    assert_eq!(navaid_band("", "112500"), Some(NavaidBand::Vhf));
+    assert_eq!(navaid_band("", ""), None);
+    assert_eq!(
+        serde_json::to_string(&NavaidBand::LfMf).unwrap(),
+        "\"LF/MF\""
+    );
+}
@@ -0,0 +1,20 @@
+use ourairports::BoundingBox;
+
+#[test]
+fn box_crossing_antimeridian() {
+    let bbox: BoundingBox = "170,-30,-170,0".parse().unwrap();
+    assert!(bbox.crosses_antimeridian());
+    assert!(bbox.contains(-17.0, 179.0));
+    assert!(bbox.contains(-17.0, -179.0));
+    assert!(!bbox.contains(-17.0, 0.0));
+    assert!(!bbox.contains(10.0, 179.0));
+}
+
+#[test]
+fn box_not_crossing_antimeridian() {
+    let bbox: BoundingBox = "-10,40,10,60".parse().unwrap();
+    assert!(!bbox.crosses_antimeridian());
+    assert!(bbox.contains(51.47, -0.46));
+    assert!(!bbox.contains(51.47, 179.0));
+    assert!(!bbox.contains_point(None, None));
+}
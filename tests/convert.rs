@@ -0,0 +1,1149 @@
+use std::fs;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::process::{Command, Output, Stdio};
+use std::thread;
+
+/// Runs the binary with the given arguments
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_ourairports"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+/// Runs the binary with the given arguments, writing `input` to its stdin
+fn run_with_stdin(args: &[&str], input: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_ourairports"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+/// Checks that the binary exited successfully, showing what it wrote to stderr if not
+fn assert_success(output: &Output) {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+/// Converts a fixture CSV with the given subcommand and checks the output against its golden JSON file
+fn assert_converts_to_golden(subcommand: &str, fixture: &str) {
+    let input = format!("tests/fixtures/{}.csv", fixture);
+    let output = run(&[subcommand, &input, "--pretty-print"]);
+    assert_success(&output);
+    let golden = fs::read_to_string(format!("tests/fixtures/{}.json", fixture)).unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), golden);
+}
+
+#[test]
+fn airports() {
+    assert_converts_to_golden("airport", "airports");
+}
+
+#[test]
+fn airport_frequencies() {
+    assert_converts_to_golden("airport-frequency", "airport-frequencies");
+}
+
+#[test]
+fn runways() {
+    assert_converts_to_golden("runway", "runways");
+}
+
+#[test]
+fn navaids() {
+    assert_converts_to_golden("navaid", "navaids");
+}
+
+#[test]
+fn countries() {
+    assert_converts_to_golden("country", "countries");
+}
+
+#[test]
+fn regions() {
+    assert_converts_to_golden("region", "regions");
+}
+
+#[test]
+fn malformed_row_is_a_parse_error() {
+    let output = run(&["airport", "tests/fixtures/airports_malformed.csv"]);
+    assert_eq!(output.status.code(), Some(4));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("line: 3"), "{}", stderr);
+    assert!(stderr.contains("Value must be yes or no"), "{}", stderr);
+}
+
+#[test]
+fn tail_0_still_fails_on_a_malformed_row() {
+    let output = run(&[
+        "airport",
+        "tests/fixtures/airports_malformed.csv",
+        "--tail",
+        "0",
+    ]);
+    assert_eq!(output.status.code(), Some(4));
+}
+
+#[test]
+fn keyed_output_is_sorted() {
+    let output = run(&["index-keywords", "airport", "tests/fixtures/airports.csv"]);
+    assert_success(&output);
+    let index: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_slice(&output.stdout).unwrap();
+    let keys: Vec<&String> = index.keys().collect();
+    let mut sorted_keys = keys.clone();
+    sorted_keys.sort();
+    assert_eq!(keys, sorted_keys);
+}
+
+#[test]
+fn decimals_fixes_the_digits_of_floats() {
+    let output = run(&["airport", "tests/fixtures/airports.csv", "--decimals", "2"]);
+    assert_success(&output);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    for field in ["\"latitude_deg\":", "\"longitude_deg\":"].iter() {
+        for value in stdout.split(field).skip(1) {
+            let number = value.split(&[',', '}'][..]).next().unwrap();
+            if number != "null" {
+                assert_eq!(
+                    number.split('.').nth(1).map(str::len),
+                    Some(2),
+                    "{}",
+                    number
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn compute_adds_a_field_or_null() {
+    let output = run(&[
+        "airport",
+        "tests/fixtures/airports.csv",
+        "--compute",
+        "elev_m=elevation_ft * 0.5",
+        "--compute",
+        "unknown=no_such_field + 1",
+    ]);
+    assert_success(&output);
+    let records: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    for record in &records {
+        let expected = record["elevation_ft"]
+            .as_f64()
+            .map(|elevation| elevation * 0.5);
+        assert_eq!(record["elev_m"].as_f64(), expected);
+        assert!(record["unknown"].is_null());
+    }
+}
+
+#[test]
+fn exclude_fields_drops_only_the_named_fields() {
+    let output = run(&[
+        "airport",
+        "tests/fixtures/airports.csv",
+        "--exclude-fields",
+        "keywords,home_link",
+    ]);
+    assert_success(&output);
+    let records: Vec<serde_json::Map<String, serde_json::Value>> =
+        serde_json::from_slice(&output.stdout).unwrap();
+    for record in &records {
+        assert!(!record.contains_key("keywords"));
+        assert!(!record.contains_key("home_link"));
+        assert!(record.contains_key("wikipedia_link"));
+    }
+}
+
+#[test]
+fn fields_file_selects_the_listed_fields() {
+    let fields_file =
+        std::env::temp_dir().join(format!("ourairports-fields-{}", std::process::id()));
+    fs::write(&fields_file, "ident\nname,iata_code\n\n").unwrap();
+    let output = run(&[
+        "airport",
+        "tests/fixtures/airports.csv",
+        "--fields-file",
+        fields_file.to_str().unwrap(),
+    ]);
+    fs::remove_file(&fields_file).unwrap();
+    assert_success(&output);
+    let records: Vec<serde_json::Map<String, serde_json::Value>> =
+        serde_json::from_slice(&output.stdout).unwrap();
+    let keys: Vec<&str> = records[0].keys().map(String::as_str).collect();
+    assert_eq!(keys, ["ident", "name", "iata_code"]);
+}
+
+#[test]
+fn sort_by_elevation_descending_within_a_country() {
+    let output = run(&[
+        "airport",
+        "tests/fixtures/airports.csv",
+        "--country",
+        "US",
+        "--sort-by",
+        "elevation_ft",
+        "--desc",
+        "--head",
+        "1",
+    ]);
+    assert_success(&output);
+    let airports: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(airports.as_array().unwrap().len(), 1);
+    assert_eq!(airports[0]["ident"], "KJFK");
+}
+
+#[test]
+fn fail_on_empty_rejects_data_without_records() {
+    let input = std::env::temp_dir().join(format!("ourairports-empty-{}.csv", std::process::id()));
+    fs::write(
+        &input,
+        "\"id\",\"code\",\"name\",\"continent\",\"wikipedia_link\",\"keywords\"\n\n",
+    )
+    .unwrap();
+    let input = input.to_str().unwrap();
+
+    let output = run(&["country", input]);
+    assert_success(&output);
+    assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "[]");
+    let output = run(&["country", input, "--fail-on-empty"]);
+    fs::remove_file(input).unwrap();
+    assert_eq!(output.status.code(), Some(5));
+    // filtering out every record isn't empty data
+    let filtered = run(&[
+        "country",
+        "tests/fixtures/countries.csv",
+        "--fail-on-empty",
+        "--country",
+        "XX",
+    ]);
+    assert_success(&filtered);
+}
+
+#[test]
+fn limit_per_group_caps_each_group() {
+    let output = run(&[
+        "airport",
+        "tests/fixtures/airports.csv",
+        "--group-by",
+        "iso_country",
+        "--limit-per-group",
+        "1",
+    ]);
+    assert_success(&output);
+    let groups: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_slice(&output.stdout).unwrap();
+    assert!(!groups.is_empty());
+    for group in groups.values() {
+        assert_eq!(group.as_array().unwrap().len(), 1);
+    }
+}
+
+#[test]
+fn with_line_adds_the_csv_line_of_each_record() {
+    let output = run(&["country", "tests/fixtures/countries.csv", "--with-line"]);
+    assert_success(&output);
+    let records: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    let lines: Vec<u64> = records
+        .iter()
+        .map(|record| record["_source_line"].as_u64().unwrap())
+        .collect();
+    // line 1 is the header
+    assert_eq!(lines, (2..2 + records.len() as u64).collect::<Vec<_>>());
+}
+
+#[test]
+fn cached_output_is_reused() {
+    let cache_dir = std::env::temp_dir().join(format!("ourairports-cache-{}", std::process::id()));
+    let cache_arg = cache_dir.to_str().unwrap();
+    let args = [
+        "region",
+        "tests/fixtures/regions.csv",
+        "--cache-dir",
+        cache_arg,
+    ];
+    let first = run(&args);
+    assert_success(&first);
+    let cached: Vec<_> = fs::read_dir(&cache_dir).unwrap().collect();
+    assert_eq!(cached.len(), 1);
+    let second = run(&args);
+    assert_eq!(first.stdout, second.stdout);
+    fs::remove_dir_all(cache_dir).unwrap();
+}
+
+#[test]
+fn cached_records_are_reused_for_another_output_with_fresh_metadata() {
+    let dir =
+        std::env::temp_dir().join(format!("ourairports-cache-outputs-{}", std::process::id()));
+    let cache_dir = dir.join("cache");
+    fs::create_dir_all(&dir).unwrap();
+    let convert = |name: &str| {
+        let input = dir.join(format!("{}.csv", name));
+        fs::copy("tests/fixtures/regions.csv", &input).unwrap();
+        let output_file = dir.join(format!("{}.json", name));
+        let output = run(&[
+            "region",
+            input.to_str().unwrap(),
+            "--with-metadata",
+            "--cache-dir",
+            cache_dir.to_str().unwrap(),
+            "-o",
+            output_file.to_str().unwrap(),
+        ]);
+        assert_success(&output);
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(output_file).unwrap()).unwrap();
+        (input, json)
+    };
+    let (_, first) = convert("first");
+    let (input, second) = convert("second");
+    assert_eq!(fs::read_dir(&cache_dir).unwrap().count(), 1);
+    assert_eq!(first["records"], second["records"]);
+    assert_eq!(second["source"]["url"], input.to_str().unwrap());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn only_with_iata_drops_airports_without_an_iata_code() {
+    let output = run(&["airport", "tests/fixtures/airports.csv", "--only-with-iata"]);
+    assert_success(&output);
+    let records: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(!records.is_empty());
+    assert!(records
+        .iter()
+        .all(|record| !record["iata_code"].as_str().unwrap().is_empty()));
+}
+
+#[test]
+fn pretty_sorted_keys_sorts_every_object() {
+    let output = run(&[
+        "region",
+        "tests/fixtures/regions.csv",
+        "--pretty-sorted-keys",
+    ]);
+    assert_success(&output);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("\n  {\n"));
+    let records: Vec<serde_json::Map<String, serde_json::Value>> =
+        serde_json::from_str(&stdout).unwrap();
+    for record in &records {
+        let keys: Vec<&String> = record.keys().collect();
+        let mut sorted_keys = keys.clone();
+        sorted_keys.sort();
+        assert_eq!(keys, sorted_keys);
+    }
+}
+
+#[test]
+fn tuples_have_a_row_per_record_in_column_order() {
+    let output = run(&[
+        "country",
+        "tests/fixtures/countries.csv",
+        "--format",
+        "tuples",
+        "--fields",
+        "code,name",
+    ]);
+    assert_success(&output);
+    let tuples: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(tuples["columns"], serde_json::json!(["code", "name"]));
+    assert_eq!(
+        tuples["rows"][0],
+        serde_json::json!(["GB", "United Kingdom"])
+    );
+}
+
+#[test]
+fn frequencies_of_one_airport() {
+    let output = run(&[
+        "frequencies",
+        "--airport",
+        "EGLL",
+        "tests/fixtures/airport-frequencies.csv",
+    ]);
+    assert_success(&output);
+    let records: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(!records.is_empty());
+    assert!(records
+        .iter()
+        .all(|record| record["airport_ident"] == "EGLL"));
+
+    let output = run(&[
+        "frequencies",
+        "--airport",
+        "XXXX",
+        "tests/fixtures/airport-frequencies.csv",
+    ]);
+    assert_success(&output);
+    assert_eq!(String::from_utf8(output.stdout).unwrap().trim(), "[]");
+}
+
+#[test]
+fn trim_removes_padding_from_every_field() {
+    let output = run(&["country", "tests/fixtures/countries_padded.csv", "--trim"]);
+    assert_success(&output);
+    let records: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(records[0]["code"], "GB");
+    assert_eq!(records[0]["name"], "United Kingdom");
+    assert_eq!(records[0]["continent"], "EU");
+    assert_eq!(
+        records[0]["keywords"],
+        serde_json::json!(["Great Britain", "UK"])
+    );
+}
+
+#[test]
+fn max_bytes_drops_the_partial_last_row() {
+    let body = fs::read("tests/fixtures/airports.csv").unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/airports.csv", listener.local_addr().unwrap());
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = [0; 1024];
+        let _ = stream.read(&mut request).unwrap();
+        let header = format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            body.len()
+        );
+        stream.write_all(header.as_bytes()).unwrap();
+        let _ = stream.write_all(&body);
+    });
+    // the header, the first row and part of the second
+    let fixture = fs::read_to_string("tests/fixtures/airports.csv").unwrap();
+    let first_rows_len: usize = fixture.lines().take(2).map(|line| line.len() + 1).sum();
+    let max_bytes = (first_rows_len + 50).to_string();
+
+    let output = run(&["airport", "--from-url", &url, "--max-bytes", &max_bytes]);
+    server.join().unwrap();
+    assert_success(&output);
+    let airports: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(airports.as_array().unwrap().len(), 1);
+}
+
+#[test]
+fn downloaded_records_are_converted_before_the_download_finishes() {
+    use flate2::read::GzDecoder;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    let body = fs::read("tests/fixtures/airports.csv").unwrap();
+    // the header and the first row, then the rest once the conversion has finished
+    let fixture = fs::read_to_string("tests/fixtures/airports.csv").unwrap();
+    let split: usize = fixture.lines().take(2).map(|line| line.len() + 1).sum();
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/airports.csv", listener.local_addr().unwrap());
+    let (converted, wait_for_conversion) = mpsc::channel();
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = [0; 1024];
+        let _ = stream.read(&mut request).unwrap();
+        let header = format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            body.len()
+        );
+        stream.write_all(header.as_bytes()).unwrap();
+        stream.write_all(&body[..split]).unwrap();
+        // when the whole body is downloaded first, the conversion only finishes once the rest is sent
+        let finished_first = wait_for_conversion
+            .recv_timeout(Duration::from_secs(10))
+            .is_ok();
+        let _ = stream.write_all(&body[split..]);
+        finished_first
+    });
+
+    let output = run(&[
+        "airport",
+        "--from-url",
+        &url,
+        "--format",
+        "jsonl-gz",
+        "--head",
+        "1",
+    ]);
+    converted.send(()).unwrap();
+    assert!(server.join().unwrap());
+    assert_success(&output);
+    let mut lines = String::new();
+    GzDecoder::new(&output.stdout[..])
+        .read_to_string(&mut lines)
+        .unwrap();
+    let airport: serde_json::Value = serde_json::from_str(lines.trim_end()).unwrap();
+    assert_eq!(airport["ident"], "EGLL");
+}
+
+#[test]
+fn jsonl_gz_writes_one_record_per_line() {
+    use flate2::read::GzDecoder;
+
+    let output = run(&[
+        "airport",
+        "tests/fixtures/airports.csv",
+        "--format",
+        "jsonl-gz",
+    ]);
+    assert_success(&output);
+    let mut lines = String::new();
+    GzDecoder::new(&output.stdout[..])
+        .read_to_string(&mut lines)
+        .unwrap();
+    let airports: Vec<serde_json::Value> = lines
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(airports[0]["ident"], "EGLL");
+    assert_eq!(airports.len(), 4);
+}
+
+#[test]
+fn sample_with_a_seed_is_reproducible() {
+    let sample = |seed: &str| {
+        let output = run(&[
+            "airport",
+            "tests/fixtures/airports.csv",
+            "--sample",
+            "2",
+            "--seed",
+            seed,
+        ]);
+        assert_success(&output);
+        serde_json::from_slice::<Vec<serde_json::Value>>(&output.stdout).unwrap()
+    };
+    let first = sample("7");
+    let idents: Vec<&str> = first
+        .iter()
+        .map(|record| record["ident"].as_str().unwrap())
+        .collect();
+    // pinned, so that a change of generator or platform that changes the sample fails
+    assert_eq!(idents, ["KJFK", "00A"]);
+    assert_eq!(first, sample("7"));
+}
+
+#[cfg(feature = "bson")]
+#[test]
+fn bson_writes_a_document_per_record_with_int64_ids() {
+    let output = run(&["airport", "tests/fixtures/airports.csv", "--format", "bson"]);
+    assert_success(&output);
+    let mut reader = &output.stdout[..];
+    let mut documents = Vec::new();
+    while !reader.is_empty() {
+        documents.push(bson::Document::from_reader(&mut reader).unwrap());
+    }
+    assert_eq!(documents.len(), 4);
+    assert_eq!(documents[0].get_i64("id"), Ok(2434));
+    assert_eq!(documents[0].get_str("ident"), Ok("EGLL"));
+}
+
+#[test]
+fn exclude_country_is_applied_after_country() {
+    let idents = |args: &[&str]| {
+        let output = run(&[&["airport", "tests/fixtures/airports.csv"], args].concat());
+        assert_success(&output);
+        let airports: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+        airports
+            .iter()
+            .map(|airport| airport["ident"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(idents(&["--exclude-country", "us"]), ["EGLL", "NFFN"]);
+    assert_eq!(
+        idents(&[
+            "--country",
+            "GB",
+            "--country",
+            "US",
+            "--exclude-country",
+            "US"
+        ]),
+        ["EGLL"]
+    );
+    assert_eq!(
+        run(&["airport", "--exclude-country", "USA"]).status.code(),
+        Some(1)
+    );
+}
+
+#[test]
+fn tristate_scheduled_writes_blank_as_null() {
+    let input = std::env::temp_dir().join(format!(
+        "ourairports-blank-scheduled-{}.csv",
+        std::process::id()
+    ));
+    let fixture = fs::read_to_string("tests/fixtures/airports.csv").unwrap();
+    fs::write(
+        &input,
+        fixture.replace("\"London\",\"yes\"", "\"London\",\"\""),
+    )
+    .unwrap();
+    let input = input.to_str().unwrap();
+
+    assert!(!run(&["airport", input]).status.success());
+    let output = run(&["airport", input, "--tristate-scheduled"]);
+    assert_success(&output);
+    let airports: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(airports[0]["scheduled_service"], serde_json::Value::Null);
+    assert_eq!(airports[1]["scheduled_service"], true);
+}
+
+#[test]
+fn bundle_to_dir_skips_existing_files() {
+    let output_dir =
+        std::env::temp_dir().join(format!("ourairports-bundle-{}", std::process::id()));
+    fs::create_dir_all(&output_dir).unwrap();
+    fs::write(output_dir.join("runways.json"), "[]").unwrap();
+    let args = [
+        "bundle",
+        "--input-dir",
+        "tests/fixtures",
+        "--output-dir",
+        output_dir.to_str().unwrap(),
+    ];
+
+    let output = run(&args);
+    assert_success(&output);
+    assert_eq!(
+        fs::read_to_string(output_dir.join("runways.json")).unwrap(),
+        "[]"
+    );
+    let airports: Vec<serde_json::Value> =
+        serde_json::from_str(&fs::read_to_string(output_dir.join("airports.json")).unwrap())
+            .unwrap();
+    assert_eq!(airports.len(), 4);
+
+    let output = run(&[&args[..], &["--force"]].concat());
+    assert_success(&output);
+    assert_ne!(
+        fs::read_to_string(output_dir.join("runways.json")).unwrap(),
+        "[]"
+    );
+    fs::remove_dir_all(&output_dir).unwrap();
+}
+
+#[test]
+fn distinct_counts_values_most_common_first() {
+    let output = run(&[
+        "distinct",
+        "airport",
+        "--field",
+        "iso_country",
+        "tests/fixtures/airports.csv",
+    ]);
+    assert_success(&output);
+    let value_counts: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    let counts: Vec<u64> = value_counts
+        .iter()
+        .map(|value_count| value_count["count"].as_u64().unwrap())
+        .collect();
+    let mut sorted_counts = counts.clone();
+    sorted_counts.sort_by(|a, b| b.cmp(a));
+    assert_eq!(counts, sorted_counts);
+    assert_eq!(counts.iter().sum::<u64>(), 4);
+
+    let output = run(&[
+        "distinct",
+        "airport",
+        "--field",
+        "unknown",
+        "tests/fixtures/airports.csv",
+    ]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn with_metadata_includes_the_attribution() {
+    let output = run(&["country", "tests/fixtures/countries.csv", "--with-metadata"]);
+    assert_success(&output);
+    let wrapped: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(wrapped["attribution"]
+        .as_str()
+        .unwrap()
+        .contains("ourairports.com"));
+    assert!(wrapped["license"].is_string());
+
+    let output = run(&[
+        "airport",
+        "tests/fixtures/airports.csv",
+        "--geojson",
+        "--with-metadata",
+    ]);
+    assert_success(&output);
+    let collection: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(collection["type"], "FeatureCollection");
+    assert!(collection["properties"]["attribution"].is_string());
+    assert!(collection["properties"]["source"]["url"].is_string());
+}
+
+#[test]
+fn bundle_names_the_dataset_that_failed() {
+    let input_dir =
+        std::env::temp_dir().join(format!("ourairports-bundle-input-{}", std::process::id()));
+    fs::create_dir_all(&input_dir).unwrap();
+    for name in &[
+        "airports",
+        "airport-frequencies",
+        "runways",
+        "navaids",
+        "countries",
+    ] {
+        let file_name = format!("{}.csv", name);
+        fs::copy(
+            format!("tests/fixtures/{}", file_name),
+            input_dir.join(&file_name),
+        )
+        .unwrap();
+    }
+
+    let output = run(&["bundle", "--input-dir", input_dir.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(3));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Could not convert regions"), "{}", stderr);
+    fs::remove_dir_all(&input_dir).unwrap();
+}
+
+#[test]
+fn compact_floats_drops_float_noise() {
+    let output = run(&[
+        "country",
+        "tests/fixtures/countries.csv",
+        "--compute",
+        "sum=0.1 + 0.2",
+        "--fields",
+        "code,sum",
+        "--head",
+        "1",
+        "--compact-floats",
+    ]);
+    assert_success(&output);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains(r#""sum":0.3}"#), "{}", stdout);
+}
+
+#[test]
+fn geojson_split_skips_continents_without_coordinates() {
+    let input = std::env::temp_dir().join(format!(
+        "ourairports-split-coordinates-{}.csv",
+        std::process::id()
+    ));
+    let fixture = fs::read_to_string("tests/fixtures/airports.csv").unwrap();
+    fs::write(&input, fixture.replace("51.4706,-0.461941", ",")).unwrap();
+    let output_dir = std::env::temp_dir().join(format!("ourairports-split-{}", std::process::id()));
+    let output = run(&[
+        "airport",
+        input.to_str().unwrap(),
+        "--geojson",
+        "--split-by",
+        "continent",
+        "--output-dir",
+        output_dir.to_str().unwrap(),
+    ]);
+    assert_success(&output);
+    assert!(!output_dir.join("EU.geojson").exists());
+    let collection: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(output_dir.join("NA.geojson")).unwrap()).unwrap();
+    assert_eq!(collection["features"].as_array().unwrap().len(), 2);
+    fs::remove_dir_all(&output_dir).unwrap();
+    fs::remove_file(&input).unwrap();
+}
+
+#[test]
+fn since_keeps_only_added_and_changed_records() {
+    let fixture = fs::read_to_string("tests/fixtures/airports.csv").unwrap();
+    let lines: Vec<&str> = fixture.lines().collect();
+    // an earlier dump without KJFK, with another name for EGLL and with a record removed since
+    let previous = [
+        lines[0].to_string(),
+        lines[1].replace("London Heathrow Airport", "Heathrow"),
+        lines[3].to_string(),
+        lines[4].to_string(),
+        lines[4].replacen("4993", "9999", 1),
+    ]
+    .join("\n");
+    let dir = std::env::temp_dir().join(format!("ourairports-since-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let previous_path = dir.join("airports.csv");
+    fs::write(&previous_path, previous).unwrap();
+    let removed_path = dir.join("removed.json");
+
+    let output = run(&[
+        "airport",
+        "tests/fixtures/airports.csv",
+        "--since",
+        previous_path.to_str().unwrap(),
+        "--removed-output",
+        removed_path.to_str().unwrap(),
+    ]);
+    assert_success(&output);
+    let records: Vec<serde_json::Value> = serde_json::from_slice(&output.stdout).unwrap();
+    let idents: Vec<&str> = records
+        .iter()
+        .map(|record| record["ident"].as_str().unwrap())
+        .collect();
+    assert_eq!(idents, ["EGLL", "KJFK"]);
+    let removed: Vec<String> =
+        serde_json::from_str(&fs::read_to_string(&removed_path).unwrap()).unwrap();
+    assert_eq!(removed, ["9999"]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn dry_run_runs_the_conversion_without_output() {
+    let output = run(&[
+        "--verbose",
+        "airport",
+        "tests/fixtures/airports.csv",
+        "--dry-run",
+        "--country",
+        "US",
+    ]);
+    assert_success(&output);
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Parsed 2 records"));
+
+    let output = run(&[
+        "airport",
+        "tests/fixtures/airports.csv",
+        "--dry-run",
+        "--max-rows",
+        "2",
+    ]);
+    assert_eq!(output.status.code(), Some(5));
+
+    // an earlier dump with every record but KJFK
+    let fixture = fs::read_to_string("tests/fixtures/airports.csv").unwrap();
+    let previous: Vec<&str> = fixture
+        .lines()
+        .filter(|line| !line.contains("\"KJFK\""))
+        .collect();
+    let previous_path =
+        std::env::temp_dir().join(format!("ourairports-dry-run-{}.csv", std::process::id()));
+    fs::write(&previous_path, previous.join("\n")).unwrap();
+    let output = run(&[
+        "--verbose",
+        "airport",
+        "tests/fixtures/airports.csv",
+        "--dry-run",
+        "--since",
+        previous_path.to_str().unwrap(),
+    ]);
+    fs::remove_file(&previous_path).unwrap();
+    assert_success(&output);
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Parsed 1 records"));
+}
+
+#[test]
+fn since_is_not_supported_with_head_tail_or_sample() {
+    for limit in &["--head", "--tail", "--sample"] {
+        let output = run(&[
+            "airport",
+            "tests/fixtures/airports.csv",
+            "--since",
+            "tests/fixtures/airports.csv",
+            limit,
+            "1",
+        ]);
+        assert_eq!(output.status.code(), Some(1), "{}", limit);
+    }
+}
+
+#[test]
+fn text_output_ends_lines_in_lf_for_crlf_input() {
+    let fixture = fs::read_to_string("tests/fixtures/countries.csv").unwrap();
+    let crlf_path =
+        std::env::temp_dir().join(format!("ourairports-crlf-{}.csv", std::process::id()));
+    fs::write(&crlf_path, fixture.replace('\n', "\r\n")).unwrap();
+
+    for format in &["csv", "tsv", "sql", "json"] {
+        let lf = run(&[
+            "country",
+            "tests/fixtures/countries.csv",
+            "--format",
+            format,
+        ]);
+        let crlf = run(&["country", crlf_path.to_str().unwrap(), "--format", format]);
+        assert_success(&crlf);
+        assert!(!crlf.stdout.contains(&b'\r'), "{}", format);
+        assert_eq!(crlf.stdout, lf.stdout, "{}", format);
+    }
+    fs::remove_file(&crlf_path).unwrap();
+}
+
+#[test]
+fn with_count_wraps_the_records() {
+    let output = run(&[
+        "airport",
+        "tests/fixtures/airports.csv",
+        "--with-count",
+        "--head",
+        "2",
+    ]);
+    assert_success(&output);
+    let wrapped: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(wrapped["count"], 2);
+    assert_eq!(wrapped["records"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn blank_trailing_lines_are_skipped() {
+    let output = run(&["country", "tests/fixtures/countries_trailing_blank.csv"]);
+    assert_success(&output);
+    let expected = run(&["country", "tests/fixtures/countries.csv"]);
+    assert_eq!(output.stdout, expected.stdout);
+}
+
+#[test]
+fn navaids_group_by_associated_airport() {
+    let output = run(&[
+        "navaid",
+        "tests/fixtures/navaids.csv",
+        "--group-by",
+        "associated_airport",
+    ]);
+    assert_success(&output);
+    let groups: serde_json::Map<String, serde_json::Value> =
+        serde_json::from_slice(&output.stdout).unwrap();
+    let keys: Vec<&str> = groups.keys().map(String::as_str).collect();
+    assert_eq!(keys, ["", "EGLL", "NFFN"]);
+    assert_eq!(groups["EGLL"][0]["ident"], "LON");
+}
+
+#[test]
+fn several_outputs_are_each_written_in_the_format_of_their_extension() {
+    let dir = std::env::temp_dir().join(format!("ourairports-outputs-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let (json_path, geojson_path, csv_path) = (
+        dir.join("airports.json"),
+        dir.join("airports.geojson"),
+        dir.join("airports.csv"),
+    );
+    let mut args = vec!["airport", "tests/fixtures/airports.csv"];
+    for path in [&json_path, &geojson_path, &csv_path] {
+        args.extend(["-o", path.to_str().unwrap()]);
+    }
+    let output = run(&args);
+    assert_success(&output);
+    for (path, format_args) in [
+        (&json_path, &[][..]),
+        (&geojson_path, &["--geojson"][..]),
+        (&csv_path, &["--format", "csv"][..]),
+    ] {
+        let expected =
+            run(&[&["airport", "tests/fixtures/airports.csv"][..], format_args].concat());
+        // stdout ends in a newline, which files written with --output don't
+        let written = fs::read_to_string(path).unwrap();
+        assert_eq!(
+            written,
+            String::from_utf8(expected.stdout).unwrap().trim_end()
+        );
+    }
+
+    args.extend(["--format", "csv"]);
+    let output = run(&args);
+    assert!(!output.status.success());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn repl_answers_each_query_until_eof() {
+    let output = run_with_stdin(
+        &["repl", "airport", "tests/fixtures/airports.csv"],
+        b"count\nunknown query\ncountry us\ntype heliport\n",
+    );
+    assert_success(&output);
+    let results: Vec<serde_json::Value> = String::from_utf8(output.stdout)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0], 4);
+    assert_eq!(results[1].as_array().unwrap().len(), 2);
+    assert_eq!(results[2][0]["ident"], "00A");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("invalid query unknown query"));
+}
+
+#[test]
+fn max_rows_counts_every_record_even_with_head() {
+    let output = run(&[
+        "airport",
+        "tests/fixtures/airports.csv",
+        "--max-rows",
+        "2",
+        "--head",
+        "1",
+    ]);
+    assert_eq!(output.status.code(), Some(5));
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("input has 4 records, more than the maximum of 2"));
+
+    let output = run(&[
+        "airport",
+        "tests/fixtures/airports.csv",
+        "--max-rows",
+        "4",
+        "--head",
+        "1",
+    ]);
+    assert_success(&output);
+}
+
+#[test]
+fn append_adds_the_records_of_each_run_to_the_file() {
+    use flate2::read::MultiGzDecoder;
+
+    let path = std::env::temp_dir().join(format!(
+        "ourairports-append-{}.jsonl.gz",
+        std::process::id()
+    ));
+    let _ = fs::remove_file(&path);
+    for country in ["US", "GB"] {
+        let output = run(&[
+            "airport",
+            "tests/fixtures/airports.csv",
+            "--format",
+            "jsonl-gz",
+            "--country",
+            country,
+            "--append",
+            "-o",
+            path.to_str().unwrap(),
+        ]);
+        assert_success(&output);
+    }
+    let mut lines = String::new();
+    MultiGzDecoder::new(&fs::read(&path).unwrap()[..])
+        .read_to_string(&mut lines)
+        .unwrap();
+    let idents: Vec<String> = lines
+        .lines()
+        .map(|line| {
+            serde_json::from_str::<serde_json::Value>(line).unwrap()["ident"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        })
+        .collect();
+    assert_eq!(idents, ["KJFK", "00A", "EGLL"]);
+
+    let output = run(&[
+        "airport",
+        "tests/fixtures/airports.csv",
+        "--append",
+        "-o",
+        path.to_str().unwrap(),
+    ]);
+    assert!(!output.status.success());
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn append_leaves_the_file_as_it_was_when_the_output_is_too_large() {
+    use flate2::read::MultiGzDecoder;
+
+    let path = std::env::temp_dir().join(format!(
+        "ourairports-append-capped-{}.jsonl.gz",
+        std::process::id()
+    ));
+    let _ = fs::remove_file(&path);
+    let append = |extra_args: &[&str]| {
+        let mut args = vec![
+            "airport",
+            "tests/fixtures/airports.csv",
+            "--format",
+            "jsonl-gz",
+            "--append",
+            "-o",
+            path.to_str().unwrap(),
+        ];
+        args.extend_from_slice(extra_args);
+        run(&args)
+    };
+    assert_success(&append(&["--country", "GB"]));
+    let before = fs::read(&path).unwrap();
+
+    let output = append(&["--max-output-bytes", "50"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--max-output-bytes 50"));
+    let after = fs::read(&path).unwrap();
+    assert_eq!(after, before);
+    let mut lines = String::new();
+    MultiGzDecoder::new(&after[..])
+        .read_to_string(&mut lines)
+        .unwrap();
+    assert_eq!(lines.lines().count(), 1);
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn max_output_bytes_fails_on_larger_output() {
+    let output = run(&[
+        "airport",
+        "tests/fixtures/airports.csv",
+        "--max-output-bytes",
+        "100",
+    ]);
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--max-output-bytes 100"));
+
+    let output = run(&[
+        "--verbose",
+        "airport",
+        "tests/fixtures/airports.csv",
+        "--max-output-bytes",
+        "100000",
+    ]);
+    assert_success(&output);
+    // the newline at the end of stdout is part of the output's size, as when streamed
+    let size = output.stdout.len();
+    let wrote = format!("Wrote {} bytes", size);
+    assert!(String::from_utf8_lossy(&output.stderr).contains(&wrote));
+
+    let max_output_bytes = |max_bytes: usize| {
+        run(&[
+            "airport",
+            "tests/fixtures/airports.csv",
+            "--max-output-bytes",
+            &max_bytes.to_string(),
+        ])
+    };
+    assert_success(&max_output_bytes(size));
+    assert!(!max_output_bytes(size - 1).status.success());
+}
+
+#[test]
+fn tiles_are_written_in_the_output_style_up_to_zoom_22() {
+    let dir = std::env::temp_dir().join(format!("ourairports-tiles-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let tiles = |zoom: &str| {
+        run(&[
+            "tiles",
+            "tests/fixtures/airports.csv",
+            "--zoom",
+            zoom,
+            "--output-dir",
+            dir.to_str().unwrap(),
+            "--decimals",
+            "1",
+        ])
+    };
+    let output = tiles("23");
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("expected 0 to 22"));
+
+    assert_success(&tiles("0"));
+    let tile = fs::read_to_string(dir.join("0/0/0.json")).unwrap();
+    assert!(tile.contains("\"latitude_deg\":51.5,"));
+    let airports: Vec<serde_json::Value> = serde_json::from_str(&tile).unwrap();
+    assert_eq!(airports.len(), 4);
+    fs::remove_dir_all(&dir).unwrap();
+}
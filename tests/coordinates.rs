@@ -0,0 +1,83 @@
+use ourairports::{
+    add_navaid_style, navaid_marker_symbol, topology, Airport, TOPOLOGY_QUANTIZATION,
+};
+use serde_json::json;
+
+#[test]
+fn blank_coordinates_are_none() {
+    let data = include_str!("fixtures/airports_missing_coordinates.csv");
+    let mut rdr = csv::Reader::from_reader(data.as_bytes());
+    let airports: Vec<Airport> = rdr.deserialize().collect::<Result<_, _>>().unwrap();
+    assert_eq!(airports.len(), 2);
+    assert_eq!(airports[0].latitude_deg, Some(51.4706));
+    assert_eq!(airports[0].longitude_deg, Some(-0.461941));
+    assert_eq!(airports[1].latitude_deg, None);
+    assert_eq!(airports[1].longitude_deg, None);
+}
+
+#[test]
+fn topology_has_a_point_per_located_record() {
+    let records = vec![
+        json!({"id": "1", "latitude_deg": 51.5, "longitude_deg": -0.5}),
+        json!({"id": "2", "latitude_deg": null, "longitude_deg": null}),
+    ];
+    let topology = topology("airports", &records);
+    assert_eq!(topology["type"], "Topology");
+    let geometries = topology["objects"]["airports"]["geometries"]
+        .as_array()
+        .unwrap();
+    assert_eq!(geometries.len(), 1);
+    assert_eq!(geometries[0]["coordinates"], json!([0, 0]));
+    assert_eq!(topology["transform"]["translate"], json!([-0.5, 51.5]));
+    assert_eq!(geometries[0]["properties"]["id"], "1");
+}
+
+#[test]
+fn topology_points_are_quantized_by_the_transform() {
+    let records = vec![
+        json!({"id": "1", "latitude_deg": 51.4706, "longitude_deg": -0.461941}),
+        json!({"id": "2", "latitude_deg": -17.755399703979492, "longitude_deg": 177.44299316406}),
+        json!({"id": "3", "latitude_deg": 40.639801, "longitude_deg": -73.7789}),
+    ];
+    let topology = topology("airports", &records);
+    assert_eq!(
+        topology["bbox"],
+        json!([-73.7789, -17.755399703979492, 177.44299316406, 51.4706])
+    );
+    let transform = &topology["transform"];
+    let geometries = topology["objects"]["airports"]["geometries"]
+        .as_array()
+        .unwrap();
+    let max = u64::from(TOPOLOGY_QUANTIZATION - 1);
+    for (geometry, record) in geometries.iter().zip(&records) {
+        for (axis, field) in ["longitude_deg", "latitude_deg"].iter().enumerate() {
+            let quantized = geometry["coordinates"][axis].as_u64().unwrap();
+            assert!(quantized <= max);
+            let scale = transform["scale"][axis].as_f64().unwrap();
+            let position =
+                quantized as f64 * scale + transform["translate"][axis].as_f64().unwrap();
+            assert!((position - record[field].as_f64().unwrap()).abs() <= scale / 2.0);
+        }
+    }
+    assert_eq!(geometries[1]["coordinates"][0], max);
+}
+
+#[test]
+fn empty_topology_has_no_transform() {
+    let topology = topology("navaids", &[]);
+    assert!(topology.get("transform").is_none());
+    assert_eq!(topology["objects"]["navaids"]["geometries"], json!([]));
+}
+
+#[test]
+fn navaids_are_styled_by_type() {
+    let mut fields = json!({"type": "NDB", "frequency_khz": "212"})
+        .as_object()
+        .unwrap()
+        .clone();
+    add_navaid_style(&mut fields);
+    assert_eq!(fields["marker-symbol"], "circle-stroked");
+    assert_eq!(fields["frequency_mhz"], json!(0.212));
+    assert_eq!(fields["navaid_type"], "NDB");
+    assert_eq!(navaid_marker_symbol("VORTAC"), "triangle");
+}
@@ -0,0 +1,20 @@
+use ourairports::{dedupe_frequencies, parse_from_bytes, AirportFrequency};
+
+#[test]
+fn duplicate_tower_frequencies_are_merged() {
+    let data = "\"id\",\"airport_ref\",\"airport_ident\",\"type\",\"description\",\"frequency_mhz\"
+60608,2434,\"EGLL\",\"TWR\",\"TWR\",118.5
+60609,2434,\"EGLL\",\"GND\",\"HEATHROW GND\",121.9
+60610,2434,\"EGLL\",\"TWR\",\"HEATHROW TWR\",118.5
+60611,2434,\"EGLL\",\"TWR\",\"HEATHROW TWR\",118.7
+";
+    let frequencies: Vec<AirportFrequency> = parse_from_bytes(data.as_bytes()).unwrap();
+    let (frequencies, merged_count) = dedupe_frequencies(frequencies, |frequency| frequency);
+    assert_eq!(merged_count, 1);
+    let ids: Vec<&str> = frequencies
+        .iter()
+        .map(|frequency| frequency.id.as_str())
+        .collect();
+    // the longer description takes the place of the first tower frequency
+    assert_eq!(ids, ["60610", "60609", "60611"]);
+}
@@ -0,0 +1,34 @@
+use ourairports::{distance_nm, parse_from_bytes, OurAirportsData};
+
+fn fixture_data() -> OurAirportsData {
+    OurAirportsData::new(
+        parse_from_bytes(include_bytes!("fixtures/airports.csv")).unwrap(),
+        parse_from_bytes(include_bytes!("fixtures/runways.csv")).unwrap(),
+        parse_from_bytes(include_bytes!("fixtures/airport-frequencies.csv")).unwrap(),
+        parse_from_bytes(include_bytes!("fixtures/navaids.csv")).unwrap(),
+        parse_from_bytes(include_bytes!("fixtures/countries.csv")).unwrap(),
+        parse_from_bytes(include_bytes!("fixtures/regions.csv")).unwrap(),
+    )
+}
+
+#[test]
+fn distance_between_airports() {
+    // Heathrow to JFK is about 2,990 nautical miles
+    let distance = distance_nm(51.4706, -0.461941, 40.6398, -73.7789);
+    assert!((distance - 2990.0).abs() < 10.0, "{}", distance);
+}
+
+#[test]
+fn detail_joins_every_dataset() {
+    let data = fixture_data();
+    let airport = data.airport_by_icao("egll").unwrap();
+    let detail = data.airport_detail(airport, 25.0);
+    assert_eq!(detail.country_name, Some("United Kingdom"));
+    assert_eq!(detail.runways.len(), 2);
+    assert!(!detail.frequencies.is_empty());
+    assert!(!detail.nearby_navaids.is_empty());
+    assert!(detail
+        .nearby_navaids
+        .windows(2)
+        .all(|pair| pair[0].distance_nm <= pair[1].distance_nm));
+}
@@ -0,0 +1,55 @@
+#![cfg(feature = "flatgeobuf")]
+
+use flatgeobuf::{ColumnType, FallibleStreamingIterator, FeatureProperties, FgbReader};
+use ourairports::{parse_from_bytes, to_flatgeobuf, Airport};
+use std::io::Cursor;
+
+fn airports_file() -> Vec<u8> {
+    let airports: Vec<Airport> = parse_from_bytes(include_bytes!("fixtures/airports.csv")).unwrap();
+    let records: Vec<serde_json::Value> = airports
+        .iter()
+        .map(|airport| serde_json::to_value(airport).unwrap())
+        .collect();
+    to_flatgeobuf("airports", &records).unwrap()
+}
+
+#[test]
+fn airports_are_indexed_points_with_their_fields() {
+    let file = airports_file();
+    let reader = FgbReader::open(Cursor::new(&file)).unwrap();
+    let header = reader.header();
+    assert_eq!(header.name(), Some("airports"));
+    assert_eq!(header.features_count(), 4);
+    assert_eq!(header.index_node_size(), 16);
+    assert_eq!(header.crs().unwrap().code(), 4326);
+    let columns = header.columns().unwrap();
+    assert_eq!(columns.get(0).name(), "id");
+    let column_type = |name| {
+        let column = columns.iter().find(|column| column.name() == name);
+        column.unwrap().type_()
+    };
+    assert_eq!(column_type("ident"), ColumnType::String);
+    assert_eq!(column_type("latitude_deg"), ColumnType::Double);
+    assert_eq!(column_type("scheduled_service"), ColumnType::Bool);
+
+    let mut features = reader.select_all().unwrap();
+    let mut idents = Vec::new();
+    while let Some(feature) = features.next().unwrap() {
+        idents.push(feature.property::<String>("ident").unwrap());
+    }
+    idents.sort();
+    assert_eq!(idents, ["00A", "EGLL", "KJFK", "NFFN"]);
+}
+
+#[test]
+fn the_index_finds_the_airports_in_a_box() {
+    let file = airports_file();
+    let reader = FgbReader::open(Cursor::new(&file)).unwrap();
+    // around London
+    let mut features = reader.select_bbox(-1.0, 51.0, 1.0, 52.0).unwrap();
+    let mut idents = Vec::new();
+    while let Some(feature) = features.next().unwrap() {
+        idents.push(feature.property::<String>("ident").unwrap());
+    }
+    assert_eq!(idents, ["EGLL"]);
+}
@@ -0,0 +1,32 @@
+use ourairports::Runway;
+
+#[test]
+fn negative_heading_is_normalized() {
+    let data = "\"id\",\"airport_ref\",\"airport_ident\",\"length_ft\",\"width_ft\",\"surface\",\"lighted\",\"closed\",\"le_ident\",\"le_latitude_deg\",\"le_longitude_deg\",\"le_elevation_ft\",\"le_heading_degT\",\"le_displaced_threshold_ft\",\"he_ident\",\"he_latitude_deg\",\"he_longitude_deg\",\"he_elevation_ft\",\"he_heading_degT\",\"he_displaced_threshold_ft\"
+269408,6523,\"00A\",80,80,\"ASPH-G\",1,0,\"H1\",,,,-10.0,,,,,,,
+";
+    let mut rdr = csv::Reader::from_reader(data.as_bytes());
+    let mut runway: Runway = rdr.deserialize().next().unwrap().unwrap();
+    runway.normalize_headings();
+    assert_eq!(runway.le_heading_deg_true, Some(350.0));
+    assert_eq!(runway.he_heading_deg_true, None);
+}
+
+#[test]
+fn heading_consistency_is_within_the_tolerance_of_reciprocal() {
+    let data = "\"id\",\"airport_ref\",\"airport_ident\",\"length_ft\",\"width_ft\",\"surface\",\"lighted\",\"closed\",\"le_ident\",\"le_latitude_deg\",\"le_longitude_deg\",\"le_elevation_ft\",\"le_heading_degT\",\"le_displaced_threshold_ft\",\"he_ident\",\"he_latitude_deg\",\"he_longitude_deg\",\"he_elevation_ft\",\"he_heading_degT\",\"he_displaced_threshold_ft\"
+1,2434,\"EGLL\",12799,164,\"ASP\",1,0,\"09L\",,,,89.0,,\"27R\",,,,272.0,
+2,2434,\"EGLL\",12008,164,\"ASP\",1,0,\"09R\",,,,89.0,,\"27L\",,,,250.0,
+3,6523,\"00A\",80,80,\"ASPH-G\",1,0,\"H1\",,,,-10.0,,,,,,,
+";
+    let mut rdr = csv::Reader::from_reader(data.as_bytes());
+    let consistency: Vec<Option<bool>> = rdr
+        .deserialize()
+        .map(|runway| {
+            let mut runway: Runway = runway.unwrap();
+            runway.add_heading_consistency(5.0);
+            runway.heading_consistency.unwrap()
+        })
+        .collect();
+    assert_eq!(consistency, [Some(true), Some(false), None]);
+}
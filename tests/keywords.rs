@@ -0,0 +1,19 @@
+use ourairports::{set_keyword_separator, sort_keywords, Country};
+
+#[test]
+fn semicolon_separated_keywords() {
+    set_keyword_separator(';');
+    let data = "\"id\",\"code\",\"name\",\"continent\",\"wikipedia_link\",\"keywords\"
+302672,\"GB\",\"United Kingdom\",\"EU\",\"https://en.wikipedia.org/wiki/United_Kingdom\",\"Great Britain; UK, Britain\"
+";
+    let mut rdr = csv::Reader::from_reader(data.as_bytes());
+    let country: Country = rdr.deserialize().next().unwrap().unwrap();
+    assert_eq!(country.keywords, vec!["Great Britain", "UK, Britain"]);
+}
+
+#[test]
+fn keywords_sort_ignoring_case() {
+    let mut keywords = vec!["Zed".to_string(), "alpha".to_string()];
+    sort_keywords(&mut keywords);
+    assert_eq!(keywords, ["alpha", "Zed"]);
+}
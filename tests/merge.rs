@@ -0,0 +1,17 @@
+use ourairports::{parse_from_bytes, runways_with_airports, Airport, Runway};
+use serde_json::Value;
+
+#[test]
+fn runways_are_joined_with_their_airport() {
+    let runways: Vec<Runway> = parse_from_bytes(include_bytes!("fixtures/runways.csv")).unwrap();
+    let airports: Vec<Airport> = parse_from_bytes(include_bytes!("fixtures/airports.csv")).unwrap();
+    let merged = runways_with_airports(&runways, &airports).unwrap();
+    assert_eq!(merged.len(), runways.len());
+    assert_eq!(merged[0]["airport_name"], "London Heathrow Airport");
+    assert_eq!(merged[0]["airport_iso_country"], "GB");
+
+    // a runway of an airport that isn't in the data
+    let unmatched = runways_with_airports(&runways[..1], &airports[1..]).unwrap();
+    assert_eq!(unmatched[0]["airport_name"], Value::Null);
+    assert_eq!(unmatched[0]["airport_latitude_deg"], Value::Null);
+}
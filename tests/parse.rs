@@ -0,0 +1,172 @@
+use futures::StreamExt;
+use ourairports::{
+    check_columns, download_records, parse_file, parse_from_bytes, parse_from_reader, Airport,
+    AirportType, Country, Error, Navaid, NavaidType, Region, RequestType,
+};
+use std::fs;
+use std::io::{Read, Write};
+use std::net::TcpListener;
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread;
+
+#[test]
+fn parses_bytes() {
+    let countries: Vec<Country> =
+        parse_from_bytes(include_bytes!("fixtures/countries.csv")).unwrap();
+    assert_eq!(countries.len(), 3);
+    assert_eq!(countries[0].code, "GB");
+}
+
+#[test]
+fn blank_trailing_lines_are_skipped() {
+    let countries: Vec<Country> =
+        parse_from_bytes(include_bytes!("fixtures/countries_trailing_blank.csv")).unwrap();
+    assert_eq!(countries.len(), 3);
+    let from_file =
+        parse_file::<Country>(Path::new("tests/fixtures/countries_trailing_blank.csv")).unwrap();
+    assert_eq!(from_file.map(Result::unwrap).count(), 3);
+    // a short row that isn't blank still fails
+    assert!(parse_from_bytes::<Country>(b"id,code,name\n1,GB\n").is_err());
+}
+
+#[test]
+fn malformed_bytes_are_a_parse_error() {
+    match parse_from_bytes::<Region>(b"id,code\n1,GB-ENG\n") {
+        Ok(_) => panic!("parsed a region without most of its fields"),
+        Err(err) => assert!(matches!(err, Error::Deserialize { line: 2, .. })),
+    }
+}
+
+#[test]
+fn parses_a_file_as_it_is_read() {
+    let regions = parse_file::<Region>(Path::new("tests/fixtures/regions.csv")).unwrap();
+    let from_bytes: Vec<Region> = parse_from_bytes(include_bytes!("fixtures/regions.csv")).unwrap();
+    assert_eq!(regions.map(Result::unwrap).count(), from_bytes.len());
+}
+
+#[test]
+fn invalid_utf8_is_a_parse_error_of_its_record() {
+    let data: &[u8] = b"id,code,name\n1,A,Good\n2,B,Ba\xffd\n";
+    let results: Vec<_> = parse_from_reader::<(String, String, String), _>(data).collect();
+    assert!(results[0].is_ok());
+    assert!(results[1].is_err());
+}
+
+#[test]
+fn types_are_parsed_into_enums() {
+    let airports: Vec<Airport> = parse_from_bytes(include_bytes!("fixtures/airports.csv")).unwrap();
+    assert_eq!(airports[0].airport_type, AirportType::LargeAirport);
+    let navaids: Vec<Navaid> = parse_from_bytes(include_bytes!("fixtures/navaids.csv")).unwrap();
+    assert_eq!(navaids[0].navaid_type, NavaidType::VorDme);
+    assert_eq!(
+        serde_json::to_value(&navaids[0]).unwrap()["type"],
+        "VOR-DME"
+    );
+    assert_eq!(
+        "closed_airport".parse::<AirportType>().unwrap(),
+        AirportType::Closed
+    );
+    assert!("airfield".parse::<AirportType>().is_err());
+}
+
+#[test]
+fn navaids_from_newer_dumps_with_added_columns() {
+    let fixture = include_str!("fixtures/navaids.csv");
+    let mut lines = fixture.lines();
+    let header = lines.next().unwrap();
+    let row = lines.next().unwrap();
+    let newer = format!(
+        "{},\"range_nm\",\"added\"\n{},130,x\n{},,x\n",
+        header, row, row
+    );
+    let navaids: Vec<Navaid> = parse_from_bytes(newer.as_bytes()).unwrap();
+    assert_eq!(navaids[0].range_nm, Some(Some(130)));
+    // a blank range is written as null, so that every record has the field
+    assert_eq!(navaids[1].range_nm, Some(None));
+    let value = serde_json::to_value(&navaids[1]).unwrap();
+    assert_eq!(value.get("range_nm"), Some(&serde_json::Value::Null));
+
+    let older: Vec<Navaid> = parse_from_bytes(fixture.as_bytes()).unwrap();
+    assert_eq!(older[0].range_nm, None);
+}
+
+#[test]
+fn fixtures_have_every_column() {
+    let fixtures: [(RequestType, &[u8]); 6] = [
+        (
+            RequestType::Airport,
+            include_bytes!("fixtures/airports.csv"),
+        ),
+        (
+            RequestType::AirportFrequency,
+            include_bytes!("fixtures/airport-frequencies.csv"),
+        ),
+        (RequestType::Runway, include_bytes!("fixtures/runways.csv")),
+        (RequestType::Navaid, include_bytes!("fixtures/navaids.csv")),
+        (
+            RequestType::Country,
+            include_bytes!("fixtures/countries.csv"),
+        ),
+        (RequestType::Region, include_bytes!("fixtures/regions.csv")),
+    ];
+    for (request_type, bytes) in fixtures.iter() {
+        check_columns(bytes, *request_type).unwrap();
+    }
+}
+
+#[test]
+fn renamed_column_is_reported() {
+    match check_columns(b"id,name,code\n", RequestType::Region) {
+        Err(Error::MissingColumn(column, found)) => {
+            assert_eq!(column, "local_code");
+            assert_eq!(found, vec!["id", "name", "code"]);
+        }
+        _ => panic!("accepted a region header without most of its columns"),
+    }
+}
+
+#[tokio::test]
+async fn downloaded_records_flow_before_the_download_finishes() {
+    let body = fs::read("tests/fixtures/airports.csv").unwrap();
+    // the header and the first row, then the rest once the first record has been parsed
+    let split = body
+        .iter()
+        .enumerate()
+        .filter(|(_, byte)| **byte == b'\n')
+        .nth(1)
+        .unwrap()
+        .0
+        + 1;
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/airports.csv", listener.local_addr().unwrap());
+    let (first_parsed, wait_for_first) = mpsc::channel();
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut request = [0; 1024];
+        let _ = stream.read(&mut request).unwrap();
+        let header = format!(
+            "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            body.len()
+        );
+        stream.write_all(header.as_bytes()).unwrap();
+        stream.write_all(&body[..split]).unwrap();
+        wait_for_first.recv().unwrap();
+        stream.write_all(&body[split..]).unwrap();
+    });
+
+    let (mut records, source) = download_records::<Airport>(&url, RequestType::Airport)
+        .await
+        .unwrap();
+    assert_eq!(source.url, url);
+    let first = records.next().await.unwrap().unwrap();
+    assert_eq!(first.ident, "EGLL");
+    first_parsed.send(()).unwrap();
+    let mut count = 1;
+    while let Some(record) = records.next().await {
+        record.unwrap();
+        count += 1;
+    }
+    server.join().unwrap();
+    assert_eq!(count, 4);
+}
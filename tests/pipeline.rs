@@ -0,0 +1,15 @@
+use ourairports::{Airport, AirportType, Pipeline};
+
+#[test]
+fn filters_as_records_are_parsed() {
+    let data: &[u8] = include_bytes!("fixtures/airports.csv");
+    let mut json = Vec::new();
+    let record_count = Pipeline::<Airport>::new(data)
+        .filter_country("us")
+        .filter_type(AirportType::Heliport)
+        .write_json(&mut json)
+        .unwrap();
+    assert_eq!(record_count, 1);
+    let airports: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    assert_eq!(airports[0]["ident"], "00A");
+}
@@ -0,0 +1,41 @@
+use ourairports::{parse_from_bytes, Airport, Query, QueryResult};
+
+fn airports() -> Vec<Airport> {
+    parse_from_bytes(include_bytes!("fixtures/airports.csv")).unwrap()
+}
+
+#[test]
+fn queries_parse_from_words() {
+    assert_eq!(
+        "near 51 0 10".parse::<Query>().unwrap(),
+        Query::Near {
+            latitude: 51.0,
+            longitude: 0.0,
+            radius_nm: 10.0
+        }
+    );
+    assert_eq!(
+        "  country  US ".parse::<Query>().unwrap(),
+        Query::Country("US".to_string())
+    );
+    assert!("near 51 0".parse::<Query>().is_err());
+    assert!("near north 0 10".parse::<Query>().is_err());
+}
+
+#[test]
+fn near_returns_records_within_the_radius_nearest_first() {
+    let airports = airports();
+    // next to the heliport in Pennsylvania, about 50 nm from JFK and far from the others
+    let query: Query = "near 40.1 -74.9 100".parse().unwrap();
+    match query.run(&airports) {
+        QueryResult::Nearby(nearby) => {
+            let idents: Vec<&str> = nearby
+                .iter()
+                .map(|nearby| nearby.record.ident.as_str())
+                .collect();
+            assert_eq!(idents, ["00A", "KJFK"]);
+            assert!(nearby[0].distance_nm < nearby[1].distance_nm);
+        }
+        _ => panic!("expected nearby records"),
+    }
+}
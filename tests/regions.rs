@@ -0,0 +1,56 @@
+use ourairports::{
+    airports_per_region, is_unassigned_region, parse_from_bytes, regions_by_country, Airport,
+    Country, CountryRegions, Region,
+};
+
+#[test]
+fn unassigned_region() {
+    let regions: Vec<Region> = parse_from_bytes(include_bytes!("fixtures/regions.csv")).unwrap();
+    let unassigned: Vec<&str> = regions
+        .iter()
+        .filter(|region| region.is_unassigned())
+        .map(|region| region.code.as_str())
+        .collect();
+    assert_eq!(unassigned, vec!["US-U-A"]);
+    assert!(is_unassigned_region("U-A"));
+    assert!(!is_unassigned_region("GB-ENG"));
+}
+
+#[test]
+fn airports_per_region_includes_empty_regions() {
+    let airports: Vec<Airport> = parse_from_bytes(include_bytes!("fixtures/airports.csv")).unwrap();
+    let regions: Vec<Region> = parse_from_bytes(include_bytes!("fixtures/regions.csv")).unwrap();
+    let counts = airports_per_region(&airports, Some(&regions));
+    assert_eq!(counts["GB-ENG"], 1);
+    assert_eq!(counts["US-U-A"], 0);
+    assert_eq!(counts.values().sum::<usize>(), airports.len());
+    assert!(!airports_per_region(&airports, None).contains_key("US-U-A"));
+}
+
+#[test]
+fn countries_get_the_codes_of_their_regions() {
+    let mut countries: Vec<Country> =
+        parse_from_bytes(include_bytes!("fixtures/countries.csv")).unwrap();
+    let regions: Vec<Region> = parse_from_bytes(include_bytes!("fixtures/regions.csv")).unwrap();
+    // Fiji without any regions
+    let regions = regions_by_country(
+        regions
+            .into_iter()
+            .filter(|region| region.iso_country != "FJ")
+            .collect(),
+    );
+    let region_codes: Vec<Vec<String>> = countries
+        .iter_mut()
+        .map(|country| {
+            country.add_regions(&regions, false);
+            match country.regions.take() {
+                Some(CountryRegions::Codes(codes)) => codes,
+                _ => panic!("expected the codes of the regions"),
+            }
+        })
+        .collect();
+    assert_eq!(
+        region_codes,
+        vec![vec!["GB-ENG"], vec!["US-NY", "US-PA"], vec![]]
+    );
+}
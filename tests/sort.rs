@@ -0,0 +1,52 @@
+use ourairports::{parse_from_bytes, sort_by_field, Airport, Error};
+
+/// Returns the fixture airports with the elevations of EGLL and 00A unknown
+fn airports_with_unknown_elevations() -> Vec<Airport> {
+    let mut airports: Vec<Airport> =
+        parse_from_bytes(include_bytes!("fixtures/airports.csv")).unwrap();
+    for airport in &mut airports {
+        if airport.ident == "EGLL" || airport.ident == "00A" {
+            airport.elevation_ft = None;
+        }
+    }
+    airports
+}
+
+fn idents(airports: &[Airport]) -> Vec<&str> {
+    airports
+        .iter()
+        .map(|airport| airport.ident.as_str())
+        .collect()
+}
+
+#[test]
+fn unknown_elevations_sort_last_in_either_order() {
+    let ascending = sort_by_field(
+        airports_with_unknown_elevations(),
+        "elevation_ft",
+        false,
+        |airport| airport,
+    )
+    .unwrap();
+    assert_eq!(idents(&ascending), ["KJFK", "NFFN", "EGLL", "00A"]);
+
+    let descending = sort_by_field(
+        airports_with_unknown_elevations(),
+        "elevation_ft",
+        true,
+        |airport| airport,
+    )
+    .unwrap();
+    assert_eq!(idents(&descending), ["NFFN", "KJFK", "EGLL", "00A"]);
+}
+
+#[test]
+fn sorting_by_an_unknown_field_fails() {
+    let sorted = sort_by_field(
+        airports_with_unknown_elevations(),
+        "altitude",
+        false,
+        |airport| airport,
+    );
+    assert!(matches!(sorted, Err(Error::Field(_))));
+}
@@ -0,0 +1,37 @@
+use ourairports::to_sql;
+use serde_json::json;
+
+#[test]
+fn creates_a_typed_table_and_escapes_strings() {
+    let records = [
+        json!({"id": "1", "name": "Chicago O'Hare", "elevation_ft": 668, "closed": false}),
+        json!({"id": "2", "name": "Tiny", "elevation_ft": 1.5, "closed": null}),
+    ];
+    let script = to_sql(&records, "airports", None).unwrap();
+    let lines: Vec<&str> = script.lines().collect();
+    assert_eq!(
+        lines[..6],
+        [
+            "CREATE TABLE airports (",
+            "  id TEXT,",
+            "  name TEXT,",
+            "  elevation_ft DOUBLE PRECISION,",
+            "  closed BOOLEAN",
+            ");",
+        ]
+    );
+    assert_eq!(
+        lines[6],
+        "INSERT INTO airports (id, name, elevation_ft, closed) VALUES ('1', 'Chicago O''Hare', 668, FALSE);"
+    );
+    assert_eq!(
+        lines[7],
+        "INSERT INTO airports (id, name, elevation_ft, closed) VALUES ('2', 'Tiny', 1.5, NULL);"
+    );
+}
+
+#[test]
+fn no_records_is_an_empty_script() {
+    assert_eq!(to_sql(&[], "airports", None).unwrap(), "");
+    assert_eq!(to_sql(&[json!({})], "airports", None).unwrap(), "");
+}
@@ -0,0 +1,19 @@
+use ourairports::{max_runway_lengths, AircraftCategory, Runway, RunwayThresholds};
+
+#[test]
+fn longest_open_runway_of_each_airport() {
+    let runways: Vec<Runway> =
+        ourairports::parse_from_bytes(include_bytes!("fixtures/runways.csv")).unwrap();
+    let lengths = max_runway_lengths(&runways);
+    assert_eq!(lengths.get("2434"), Some(&12799));
+    // the longer runway of 00A is closed
+    assert_eq!(lengths.get("6523"), Some(&80));
+}
+
+#[test]
+fn categories_by_threshold() {
+    let thresholds = RunwayThresholds::default();
+    assert_eq!(thresholds.category(12799), AircraftCategory::HeavyJet);
+    assert_eq!(thresholds.category(5000), AircraftCategory::RegionalJet);
+    assert_eq!(thresholds.category(4999), AircraftCategory::Ga);
+}
@@ -0,0 +1,17 @@
+use ourairports::{to_delimited, to_tuples};
+use serde_json::json;
+
+#[test]
+fn columns_include_fields_missing_from_the_first_record() {
+    let records = [
+        json!({"id": "87028", "ident": "LON"}),
+        json!({"id": "86445", "ident": "BIG", "range_nm": 130}),
+    ];
+    assert_eq!(
+        to_delimited(&records, b',', None).unwrap(),
+        "id,ident,range_nm\n87028,LON,\n86445,BIG,130\n"
+    );
+    let tuples = to_tuples(&records);
+    assert_eq!(tuples["columns"], json!(["id", "ident", "range_nm"]));
+    assert_eq!(tuples["rows"][0], json!(["87028", "LON", null]));
+}
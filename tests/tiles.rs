@@ -0,0 +1,21 @@
+use ourairports::{tile_for, MAX_ZOOM};
+
+#[test]
+fn finds_the_tile_of_a_point() {
+    assert_eq!(tile_for(51.4706, -0.461941, 10), (510, 340));
+    assert_eq!(tile_for(-33.9461, 151.177, 5), (29, 19));
+    assert_eq!(tile_for(12.0, 34.0, 0), (0, 0));
+}
+
+#[test]
+fn clamps_points_on_the_edges() {
+    assert_eq!(tile_for(90.0, 180.0, 2), (3, 0));
+    assert_eq!(tile_for(-90.0, -180.0, 2), (0, 3));
+}
+
+#[test]
+fn zoom_levels_deeper_than_the_maximum_are_the_maximum() {
+    let tile = tile_for(51.4706, -0.461941, MAX_ZOOM);
+    assert_eq!(tile_for(51.4706, -0.461941, 40), tile);
+    assert!(tile.0 > 0 && tile.1 > 0);
+}
@@ -0,0 +1,107 @@
+use ourairports::{fix_csv, misplaced_airports, parse_from_bytes, swapped_coordinates, Airport};
+
+/// Returns airports in GB at the given coordinates, copied from Heathrow in the fixture
+fn gb_airports(coordinates: &[(f64, f64)]) -> Vec<Airport> {
+    let fixture = include_str!("fixtures/airports.csv");
+    let mut lines = fixture.lines();
+    let mut data = format!("{}\n", lines.next().unwrap());
+    let heathrow = lines.next().unwrap();
+    for (index, (latitude, longitude)) in coordinates.iter().enumerate() {
+        let row = heathrow
+            .replace("\"EGLL\"", &format!("\"GB{}\"", index))
+            .replace("51.4706,-0.461941", &format!("{},{}", latitude, longitude));
+        data.push_str(&row);
+        data.push('\n');
+    }
+    parse_from_bytes(data.as_bytes()).unwrap()
+}
+
+#[test]
+fn swapped_coordinates_are_misplaced() {
+    let airports = gb_airports(&[
+        (51.47, -0.46),
+        (53.35, -2.27),
+        (55.95, -3.37),
+        (52.45, -1.74),
+        (50.95, 0.94),
+        // latitude and longitude swapped
+        (-0.46, 51.47),
+    ]);
+    let misplaced = misplaced_airports(&airports);
+    assert_eq!(misplaced.len(), 1);
+    assert_eq!(misplaced[0].ident, "GB5");
+    assert_eq!(misplaced[0].iso_country, "GB");
+    assert!(misplaced[0].distance_nm > 3000.0);
+}
+
+#[test]
+fn airports_are_checked_against_the_bounds_of_their_country() {
+    let fixture = include_str!("fixtures/airports.csv");
+    let airports: Vec<Airport> = parse_from_bytes(fixture.as_bytes()).unwrap();
+    assert!(misplaced_airports(&airports).is_empty());
+
+    // Heathrow labelled as in Fiji, whose bounds cross the antimeridian
+    let relabelled = fixture.replacen("\"GB\"", "\"FJ\"", 1);
+    let airports: Vec<Airport> = parse_from_bytes(relabelled.as_bytes()).unwrap();
+    let misplaced = misplaced_airports(&airports);
+    assert_eq!(misplaced.len(), 1);
+    assert_eq!(misplaced[0].ident, "EGLL");
+    assert!(misplaced[0].distance_nm > 8000.0);
+}
+
+#[test]
+fn swapped_coordinates_are_suggested_back() {
+    let airports = gb_airports(&[
+        (51.47, -0.46),
+        (53.35, -2.27),
+        (55.95, -3.37),
+        (52.45, -1.74),
+        (50.95, 0.94),
+        (57.2, -2.2),
+        (54.65, -6.22),
+        (51.38, -2.72),
+        // swapped, putting it south of West Africa
+        (-1.74, 52.45),
+        // an impossible latitude
+        (151.17, -33.95),
+    ]);
+    let swapped = swapped_coordinates(&airports, &[]);
+    assert_eq!(swapped.len(), 2);
+    assert_eq!(swapped[0].ident, "GB8");
+    assert_eq!(swapped[0].record_type, "airport");
+    assert_eq!(
+        (
+            swapped[0].suggested_latitude_deg,
+            swapped[0].suggested_longitude_deg
+        ),
+        (52.45, -1.74)
+    );
+    assert_eq!(swapped[1].ident, "GB9");
+}
+
+#[test]
+fn fix_csv_applies_safe_corrections() {
+    let data =
+        "id,airport_ident,lighted,closed,le_ident,le_heading_degT,he_ident,he_heading_degT\n\
+                1, egll ,,0,09L,450,27R,-90\n\
+                2,EGLL,1,0,09R,89.5,27L,269.5\n";
+    let (fixed, fixes) = fix_csv(data).unwrap();
+    assert_eq!(
+        fixed,
+        "id,airport_ident,lighted,closed,le_ident,le_heading_degT,he_ident,he_heading_degT\n\
+         1,EGLL,0,0,09L,90,27R,270\n\
+         2,EGLL,1,0,09R,89.5,27L,269.5\n"
+    );
+    let fields: Vec<&str> = fixes.iter().map(|fix| fix.field.as_str()).collect();
+    assert_eq!(
+        fields,
+        [
+            "airport_ident",
+            "lighted",
+            "le_heading_degT",
+            "he_heading_degT"
+        ]
+    );
+    assert!(fixes.iter().all(|fix| fix.line == 2));
+    assert_eq!(fixes[0].before, " egll ");
+}
@@ -0,0 +1,40 @@
+use ourairports::{parse_from_bytes, set_tristate_scheduled, Airport};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+#[test]
+fn added_fields_that_are_null_round_trip() {
+    let mut airports: Vec<Airport> =
+        parse_from_bytes(include_bytes!("fixtures/airports.csv")).unwrap();
+    let airport = &mut airports[0];
+    // neither name is known, so both are added as null
+    airport.add_names(&HashMap::new(), &HashMap::new());
+    let value = Value::from(&*airport);
+    assert!(value["country_name"].is_null());
+
+    let read_back = Airport::try_from(value.clone()).unwrap();
+    assert_eq!(read_back.country_name, Some(None));
+    assert_eq!(Value::from(&read_back), value);
+    // fields that weren't added stay left out
+    assert!(value.get("max_runway_length_ft").is_none());
+    assert_eq!(read_back.max_runway_length_ft, None);
+}
+
+#[test]
+fn unknown_scheduled_service_round_trips_as_null() {
+    let fixture = include_str!("fixtures/airports.csv");
+    let blank = fixture.replace("\"London\",\"yes\"", "\"London\",\"\"");
+    set_tristate_scheduled(true);
+    let airports: Vec<Airport> = parse_from_bytes(blank.as_bytes()).unwrap();
+    set_tristate_scheduled(false);
+    assert_eq!(airports[0].scheduled_service, None);
+    assert_eq!(airports[1].scheduled_service, Some(true));
+
+    let value = Value::from(&airports[0]);
+    assert_eq!(value["scheduled_service"], Value::Null);
+    let read_back = Airport::try_from(value.clone()).unwrap();
+    assert_eq!(read_back.scheduled_service, None);
+    assert_eq!(Value::from(&read_back), value);
+    assert_eq!(Value::from(&airports[1])["scheduled_service"], true);
+}